@@ -0,0 +1,310 @@
+pub mod runner {
+    use common::solution::Solution;
+    use std::fmt::Display;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
+
+    pub const USAGE: &str =
+        "Usage: aoc2017 run [--day <N|all>] [--part <1|2|all>] [--input <FILE>]";
+
+    pub const DAYS: [u8; 22] = [
+        1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 22, 23, 25,
+    ];
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Selection {
+        All,
+        Only(u8),
+    }
+
+    impl Selection {
+        fn includes(self, i: u8) -> bool {
+            match self {
+                Selection::All => true,
+                Selection::Only(j) => i == j,
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct Command {
+        pub day: Selection,
+        pub part: Selection,
+        pub input: Option<PathBuf>,
+    }
+
+    pub struct Outcome {
+        pub day: u8,
+        pub part: u8,
+        pub answer: String,
+        pub elapsed: Duration,
+    }
+
+    fn parse_selection(flag: &str, value: Option<&str>) -> Result<Selection, String> {
+        match value {
+            None => Err(format!("Expected a value after {}", flag)),
+            Some("all") => Ok(Selection::All),
+            Some(v) => v
+                .parse()
+                .map(Selection::Only)
+                .map_err(|_| format!("Expected a number or 'all' after {}, got: {}", flag, v)),
+        }
+    }
+
+    pub fn parse_args<I, S>(args: I) -> Result<Command, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let args: Vec<S> = args.into_iter().collect();
+        let mut args = args.iter().map(|s| s.as_ref());
+        match args.next() {
+            Some("run") => {}
+            Some(s) => return Err(format!("Unrecognised command: {}", s)),
+            None => return Err("Expected a command".to_string()),
+        }
+
+        let mut command = Command {
+            day: Selection::All,
+            part: Selection::All,
+            input: None,
+        };
+        while let Some(flag) = args.next() {
+            match flag {
+                "--day" => command.day = parse_selection(flag, args.next())?,
+                "--part" => command.part = parse_selection(flag, args.next())?,
+                "--input" => match args.next() {
+                    None => return Err("Expected a file after --input".to_string()),
+                    Some(file) => command.input = Some(PathBuf::from(file)),
+                },
+                s => return Err(format!("Unrecognised argument: {}", s)),
+            }
+        }
+
+        if let Selection::Only(day) = command.day {
+            if !DAYS.contains(&day) {
+                return Err(format!("No solution for day {}", day));
+            }
+        }
+        match command.part {
+            Selection::Only(1) | Selection::Only(2) | Selection::All => {}
+            Selection::Only(p) => return Err(format!("Expected part 1 or 2, got: {}", p)),
+        }
+        if command.day == Selection::All && command.input.is_some() {
+            return Err("--input can only be used with a single --day".to_string());
+        }
+        Ok(command)
+    }
+
+    fn record<T, F>(outcomes: &mut Vec<Outcome>, day: u8, part: u8, wanted: Selection, f: F)
+    where
+        T: Display,
+        F: FnOnce() -> T,
+    {
+        if wanted.includes(part) {
+            let start = Instant::now();
+            let answer = f();
+            let elapsed = start.elapsed();
+            outcomes.push(Outcome {
+                day,
+                part,
+                answer: answer.to_string(),
+                elapsed,
+            });
+        }
+    }
+
+    // Asking for a part the day doesn't have is an error unless every day was
+    // asked for, in which case that day is just left out.
+    fn solve<S>(
+        day: u8,
+        input: &str,
+        part: Selection,
+        every_day: bool,
+    ) -> Result<Vec<Outcome>, String>
+    where
+        S: Solution,
+    {
+        if !S::HAS_PART_2 && part == Selection::Only(2) {
+            if every_day {
+                return Ok(vec![]);
+            }
+            return Err(format!("Day {} has no part 2", day));
+        }
+        let input =
+            S::parse(input).map_err(|e| format!("Could not parse input for day {}: {}", day, e))?;
+        let config = S::Config::default();
+        let mut outcomes = vec![];
+        record(&mut outcomes, day, 1, part, || S::part_1(&input, &config));
+        if S::HAS_PART_2 {
+            record(&mut outcomes, day, 2, part, || S::part_2(&input, &config));
+        }
+        Ok(outcomes)
+    }
+
+    pub fn run_day(day: u8, input: &str, part: Selection) -> Result<Vec<Outcome>, String> {
+        dispatch(day, input, part, false)
+    }
+
+    fn dispatch(
+        day: u8,
+        input: &str,
+        part: Selection,
+        every_day: bool,
+    ) -> Result<Vec<Outcome>, String> {
+        match day {
+            1 => solve::<day_1::day_1::Day1>(day, input, part, every_day),
+            2 => solve::<day_2::day_2::Day2>(day, input, part, every_day),
+            3 => solve::<day_3::day_3::Day3>(day, input, part, every_day),
+            4 => solve::<day_4::day_4::Day4>(day, input, part, every_day),
+            5 => solve::<day_5::day_5::Day5>(day, input, part, every_day),
+            6 => solve::<day_6::day_6::Day6>(day, input, part, every_day),
+            7 => solve::<day_7::day_7::Day7>(day, input, part, every_day),
+            8 => solve::<day_8::day_8::Day8>(day, input, part, every_day),
+            9 => solve::<day_9::day_9::Day9>(day, input, part, every_day),
+            10 => solve::<day_10::day_10::Day10>(day, input, part, every_day),
+            11 => solve::<day_11::day_11::Day11>(day, input, part, every_day),
+            12 => solve::<day_12::day_12::Day12>(day, input, part, every_day),
+            13 => solve::<day_13::day_13::Day13>(day, input, part, every_day),
+            14 => solve::<day_14::day_14::Day14>(day, input, part, every_day),
+            15 => solve::<day_15::day_15::Day15>(day, input, part, every_day),
+            16 => solve::<day_16::day_16::Day16>(day, input, part, every_day),
+            17 => solve::<day_17::day_17::Day17>(day, input, part, every_day),
+            18 => solve::<day_18::day_18::Day18>(day, input, part, every_day),
+            19 => solve::<day_19::day_19::Day19>(day, input, part, every_day),
+            22 => solve::<day_22::day_22::Day22>(day, input, part, every_day),
+            23 => solve::<day_23::day_23::Day23>(day, input, part, every_day),
+            25 => solve::<day_25::day_25::Day25>(day, input, part, every_day),
+            _ => Err(format!("No solution for day {}", day)),
+        }
+    }
+
+    // The puzzle inputs checked in alongside each day's crate.
+    fn default_input(day: u8) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join(format!("day_{}", day))
+            .join("input.txt")
+    }
+
+    pub fn run(command: &Command) -> Result<Vec<Outcome>, String> {
+        let days = match command.day {
+            Selection::All => DAYS.to_vec(),
+            Selection::Only(day) => vec![day],
+        };
+        let mut outcomes = vec![];
+        for day in days {
+            let path = match &command.input {
+                Some(path) => path.clone(),
+                None => default_input(day),
+            };
+            let input = common::input::from_path(&path)
+                .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+            let every_day = command.day == Selection::All;
+            outcomes.extend(dispatch(day, &input, command.part, every_day)?);
+        }
+        Ok(outcomes)
+    }
+
+    pub fn render_table(outcomes: &[Outcome]) -> String {
+        let width = outcomes
+            .iter()
+            .map(|o| o.answer.len())
+            .chain(std::iter::once("Answer".len()))
+            .max()
+            .unwrap();
+        let mut table = format!("{:>3}  {:>4}  {:<width$}  Time\n", "Day", "Part", "Answer");
+        for o in outcomes {
+            table.push_str(&format!(
+                "{:>3}  {:>4}  {:<width$}  {:.3?}\n",
+                o.day, o.part, o.answer, o.elapsed
+            ));
+        }
+        let total: Duration = outcomes.iter().map(|o| o.elapsed).sum();
+        table.push_str(&format!(
+            "{:>3}  {:>4}  {:<width$}  {:.3?}\n",
+            "", "", "Total", total
+        ));
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::runner::*;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(["run", "--day", "18", "--part", "2", "--input", "foo.txt"]),
+            Ok(Command {
+                day: Selection::Only(18),
+                part: Selection::Only(2),
+                input: Some(PathBuf::from("foo.txt")),
+            })
+        );
+        assert_eq!(
+            parse_args(["run"]),
+            Ok(Command {
+                day: Selection::All,
+                part: Selection::All,
+                input: None,
+            })
+        );
+        assert!(parse_args(["run", "--day", "20"]).is_err());
+        assert!(parse_args(["run", "--part", "3"]).is_err());
+        assert!(parse_args(["run", "--input", "foo.txt"]).is_err());
+        assert!(parse_args(["walk"]).is_err());
+    }
+
+    #[test]
+    fn test_run_day() {
+        let outcomes = run_day(1, "1122", Selection::All).unwrap();
+        let answers: Vec<_> = outcomes
+            .iter()
+            .map(|o| (o.day, o.part, o.answer.as_str()))
+            .collect();
+        assert_eq!(answers, vec![(1, 1, "3"), (1, 2, "0")]);
+
+        let outcomes = run_day(1, "1212", Selection::Only(2)).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].answer, "6");
+
+        assert!(run_day(25, "", Selection::Only(2)).is_err());
+    }
+
+    #[test]
+    fn test_run_every_part_2() {
+        let command = Command {
+            day: Selection::All,
+            part: Selection::Only(2),
+            input: None,
+        };
+        let outcomes = run(&command).unwrap();
+        let days: Vec<u8> = outcomes.iter().map(|o| o.day).collect();
+        assert_eq!(days, &DAYS[..DAYS.len() - 1]);
+        assert!(outcomes.iter().all(|o| o.part == 2));
+
+        let command = Command {
+            day: Selection::Only(25),
+            ..command
+        };
+        assert_eq!(run(&command).err().unwrap(), "Day 25 has no part 2");
+    }
+
+    #[test]
+    fn test_render_table() {
+        let outcomes = vec![Outcome {
+            day: 1,
+            part: 2,
+            answer: "1284".to_string(),
+            elapsed: Duration::from_millis(3),
+        }];
+        assert_eq!(
+            render_table(&outcomes),
+            "Day  Part  Answer  Time\n  1     2  1284    3.000ms\n           Total   3.000ms\n"
+        );
+    }
+}
@@ -0,0 +1,19 @@
+use aoc2017::runner;
+use std::process;
+
+fn main() {
+    let command = match runner::parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n{}", e, runner::USAGE);
+            process::exit(2);
+        }
+    };
+    match runner::run(&command) {
+        Ok(outcomes) => print!("{}", runner::render_table(&outcomes)),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
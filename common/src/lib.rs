@@ -0,0 +1,368 @@
+pub mod input {
+    use crate::parse::ParseError;
+    use std::fmt;
+    use std::io::{self, Read};
+    use std::path::Path;
+
+    pub enum InputError {
+        Io(io::Error),
+        Parse(ParseError),
+    }
+
+    impl From<io::Error> for InputError {
+        fn from(e: io::Error) -> InputError {
+            InputError::Io(e)
+        }
+    }
+
+    impl From<ParseError> for InputError {
+        fn from(e: ParseError) -> InputError {
+            InputError::Parse(e)
+        }
+    }
+
+    impl fmt::Display for InputError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                InputError::Io(e) => write!(f, "Could not read input: {}", e),
+                InputError::Parse(e) => write!(f, "Could not parse input: {}", e),
+            }
+        }
+    }
+
+    // Returning an error from `main` prints its Debug representation, so make
+    // that the human-readable one.
+    impl fmt::Debug for InputError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            fmt::Display::fmt(self, f)
+        }
+    }
+
+    impl std::error::Error for InputError {}
+
+    pub fn from_reader<R>(mut reader: R) -> io::Result<String>
+    where
+        R: Read,
+    {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+        Ok(input)
+    }
+
+    pub fn from_path<P>(path: P) -> io::Result<String>
+    where
+        P: AsRef<Path>,
+    {
+        std::fs::read_to_string(path)
+    }
+
+    // Reads the file named by the first command-line argument, or stdin if there
+    // is no argument (or the argument is "-").
+    pub fn from_args() -> io::Result<String> {
+        match std::env::args_os().nth(1) {
+            Some(path) if path != "-" => from_path(path),
+            _ => from_reader(io::stdin()),
+        }
+    }
+}
+
+pub mod parse {
+    use std::fmt;
+    use std::str::FromStr;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ParseError {
+        // Both one-based.
+        pub line: usize,
+        pub column: usize,
+        // `None` if we ran out of input.
+        pub token: Option<String>,
+        pub expected: String,
+    }
+
+    fn line_and_column(s: &str, offset: usize) -> (usize, usize) {
+        let before = &s[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+
+    impl ParseError {
+        // An error at byte `offset` of `s`.
+        pub fn at<E>(s: &str, offset: usize, token: Option<&str>, expected: E) -> ParseError
+        where
+            E: Into<String>,
+        {
+            let (line, column) = line_and_column(s, offset);
+            ParseError {
+                line,
+                column,
+                token: token.map(|t| t.to_string()),
+                expected: expected.into(),
+            }
+        }
+
+        // An error about the single character at byte `offset` of `s`.
+        pub fn at_char<E>(s: &str, offset: usize, expected: E) -> ParseError
+        where
+            E: Into<String>,
+        {
+            let token = s[offset..]
+                .chars()
+                .next()
+                .map(|c| &s[offset..offset + c.len_utf8()]);
+            ParseError::at(s, offset, token, expected)
+        }
+
+        // An error at the end of `s`.
+        pub fn at_end<E>(s: &str, expected: E) -> ParseError
+        where
+            E: Into<String>,
+        {
+            ParseError::at(s, s.len(), None, expected)
+        }
+
+        // Converts an error in a substring beginning at byte `offset` of `s`
+        // into an error in `s` itself.
+        pub fn shift(self, s: &str, offset: usize) -> ParseError {
+            let (line, column) = line_and_column(s, offset);
+            ParseError {
+                column: if self.line == 1 {
+                    self.column + column - 1
+                } else {
+                    self.column
+                },
+                line: self.line + line - 1,
+                ..self
+            }
+        }
+    }
+
+    impl fmt::Display for ParseError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                f,
+                "line {}, column {}: expected {}, got ",
+                self.line, self.column, self.expected
+            )?;
+            match &self.token {
+                Some(token) => write!(f, "'{}'", token),
+                None => write!(f, "end of input"),
+            }
+        }
+    }
+
+    impl std::error::Error for ParseError {}
+
+    // Runs `f` over each line of `input`, reporting errors against `input`.
+    pub fn lines<'a, T, F>(input: &'a str, mut f: F) -> Result<Vec<T>, ParseError>
+    where
+        F: FnMut(&'a str) -> Result<T, ParseError>,
+    {
+        let mut offset = 0;
+        input
+            .split_inclusive('\n')
+            .map(|l| {
+                let start = offset;
+                offset += l.len();
+                let l = l.trim_end_matches('\n').trim_end_matches('\r');
+                f(l).map_err(|e| e.shift(input, start))
+            })
+            .collect()
+    }
+
+    pub struct Token<'a> {
+        pub text: &'a str,
+        pub offset: usize,
+    }
+
+    // Whitespace-separated tokens, which remember where they came from.
+    pub struct Tokens<'a> {
+        s: &'a str,
+        pos: usize,
+    }
+
+    impl<'a> Tokens<'a> {
+        pub fn new(s: &'a str) -> Tokens<'a> {
+            Tokens { s, pos: 0 }
+        }
+
+        pub fn error<E>(&self, token: &Token, expected: E) -> ParseError
+        where
+            E: Into<String>,
+        {
+            ParseError::at(self.s, token.offset, Some(token.text), expected)
+        }
+
+        pub fn token<E>(&mut self, expected: E) -> Result<Token<'a>, ParseError>
+        where
+            E: Into<String>,
+        {
+            self.next()
+                .ok_or_else(|| ParseError::at_end(self.s, expected))
+        }
+
+        pub fn expect(&mut self, word: &str) -> Result<(), ParseError> {
+            let expected = format!("'{}'", word);
+            let token = self.token(expected.as_str())?;
+            if token.text == word {
+                Ok(())
+            } else {
+                Err(self.error(&token, expected))
+            }
+        }
+
+        pub fn parse<T, E>(&mut self, expected: E) -> Result<T, ParseError>
+        where
+            T: FromStr,
+            E: Into<String>,
+        {
+            let expected = expected.into();
+            let token = self.token(expected.as_str())?;
+            token.text.parse().map_err(|_| self.error(&token, expected))
+        }
+
+        // Parses every remaining token.
+        pub fn parse_rest<T, E>(&mut self, expected: E) -> Result<Vec<T>, ParseError>
+        where
+            T: FromStr,
+            E: Into<String>,
+        {
+            let expected = expected.into();
+            let mut result = vec![];
+            while let Some(token) = self.next() {
+                result.push(
+                    token
+                        .text
+                        .parse()
+                        .map_err(|_| self.error(&token, expected.as_str()))?,
+                );
+            }
+            Ok(result)
+        }
+
+        pub fn end(&mut self) -> Result<(), ParseError> {
+            match self.next() {
+                None => Ok(()),
+                Some(token) => Err(self.error(&token, "end of line")),
+            }
+        }
+    }
+
+    impl<'a> Iterator for Tokens<'a> {
+        type Item = Token<'a>;
+
+        fn next(&mut self) -> Option<Token<'a>> {
+            let rest = &self.s[self.pos..];
+            let start = self.pos + (rest.len() - rest.trim_start().len());
+            let rest = &self.s[start..];
+            if rest.is_empty() {
+                self.pos = self.s.len();
+                return None;
+            }
+            let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            self.pos = start + len;
+            Some(Token {
+                text: &rest[..len],
+                offset: start,
+            })
+        }
+    }
+}
+
+pub mod solution {
+    use crate::parse::ParseError;
+    use std::fmt;
+    use std::fmt::Display;
+
+    pub trait Solution {
+        type Input<'a>;
+        // Puzzle-specific knobs, such as the size of day 16's dance floor.
+        // The default is whatever the puzzle itself specifies.
+        type Config: Default;
+        type Answer1: Display;
+        type Answer2: Display;
+
+        // Day 25 has no second part.
+        const HAS_PART_2: bool = true;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError>;
+        fn part_1(input: &Self::Input<'_>, config: &Self::Config) -> Self::Answer1;
+        fn part_2(input: &Self::Input<'_>, config: &Self::Config) -> Self::Answer2;
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct NoAnswer;
+
+    impl Display for NoAnswer {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "-")
+        }
+    }
+
+    // An answer that some inputs don't have, such as a repair for a tower
+    // that isn't broken. Shown as `NoAnswer` when it's missing.
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct MaybeAnswer<T>(pub Option<T>);
+
+    impl<T: Display> Display for MaybeAnswer<T> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match &self.0 {
+                Some(answer) => answer.fmt(f),
+                None => NoAnswer.fmt(f),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse::*;
+
+    #[test]
+    fn test_tokens() {
+        let mut tokens = Tokens::new("  set a\t-12 ");
+        tokens.expect("set").unwrap();
+        let register = tokens.token("a register").unwrap();
+        assert_eq!((register.text, register.offset), ("a", 6));
+        assert_eq!(tokens.parse::<i32, _>("a number"), Ok(-12));
+        assert_eq!(tokens.end(), Ok(()));
+        assert_eq!(
+            tokens.parse::<i32, _>("a number"),
+            Err(ParseError {
+                line: 1,
+                column: 13,
+                token: None,
+                expected: "a number".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let result = lines("1 2\r\n3 x\n", |l| {
+            Tokens::new(l)
+                .map(|t| {
+                    t.text
+                        .parse::<u32>()
+                        .map_err(|_| ParseError::at(l, t.offset, Some(t.text), "a number"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        });
+        let error = result.unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 3: expected a number, got 'x'"
+        );
+    }
+
+    #[test]
+    fn test_maybe_answer() {
+        use super::solution::MaybeAnswer;
+        assert_eq!(MaybeAnswer(Some(12)).to_string(), "12");
+        assert_eq!(MaybeAnswer::<u32>(None).to_string(), "-");
+    }
+}
@@ -0,0 +1,196 @@
+pub mod cycle {
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    // The sequence `start, f(start), f(f(start)), ...` enters a loop after
+    // `mu` steps, and then repeats every `lambda` steps.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Cycle {
+        pub mu: usize,
+        pub lambda: usize,
+    }
+
+    impl Cycle {
+        // The first index whose state is the same as that at index `n`.
+        pub fn reduce(&self, n: u64) -> usize {
+            let mu = self.mu as u64;
+            if n < mu {
+                n as usize
+            } else {
+                (mu + (n - mu) % self.lambda as u64) as usize
+            }
+        }
+    }
+
+    // Tortoise and hare: constant memory, and about three calls to `f` per
+    // state before the cycle closes.
+    pub fn floyd<T, F>(start: &T, mut f: F) -> Cycle
+    where
+        T: Clone + PartialEq,
+        F: FnMut(&T) -> T,
+    {
+        let mut tortoise = f(start);
+        let mut hare = f(&tortoise);
+        while tortoise != hare {
+            tortoise = f(&tortoise);
+            let half = f(&hare);
+            hare = f(&half);
+        }
+
+        let mut mu = 0;
+        tortoise = start.clone();
+        while tortoise != hare {
+            tortoise = f(&tortoise);
+            hare = f(&hare);
+            mu += 1;
+        }
+
+        let mut lambda = 1;
+        hare = f(&tortoise);
+        while tortoise != hare {
+            hare = f(&hare);
+            lambda += 1;
+        }
+        Cycle { mu, lambda }
+    }
+
+    // Constant memory like Floyd's, but fewer calls to `f`: the tortoise
+    // teleports to the hare at each power of two.
+    pub fn brent<T, F>(start: &T, mut f: F) -> Cycle
+    where
+        T: Clone + PartialEq,
+        F: FnMut(&T) -> T,
+    {
+        let mut power = 1;
+        let mut lambda = 1;
+        let mut tortoise = start.clone();
+        let mut hare = f(start);
+        while tortoise != hare {
+            if power == lambda {
+                tortoise = hare.clone();
+                power *= 2;
+                lambda = 0;
+            }
+            hare = f(&hare);
+            lambda += 1;
+        }
+
+        let mut mu = 0;
+        tortoise = start.clone();
+        hare = start.clone();
+        for _ in 0..lambda {
+            hare = f(&hare);
+        }
+        while tortoise != hare {
+            tortoise = f(&tortoise);
+            hare = f(&hare);
+            mu += 1;
+        }
+        Cycle { mu, lambda }
+    }
+
+    // Remembers every state: one call to `f` per state, but memory for all
+    // `mu + lambda` of them.
+    pub fn hashing<T, F>(start: &T, mut f: F) -> Cycle
+    where
+        T: Clone + Hash + Eq,
+        F: FnMut(&T) -> T,
+    {
+        let mut seen = HashMap::new();
+        let mut state = start.clone();
+        let mut i = 0;
+        loop {
+            let next = f(&state);
+            if let Some(mu) = seen.insert(state, i) {
+                return Cycle { mu, lambda: i - mu };
+            }
+            state = next;
+            i += 1;
+        }
+    }
+
+    // The state after `n` steps, which takes at most `mu + lambda` calls to
+    // `f` whatever `n` is.
+    pub fn nth_state<T, F>(start: &T, mut f: F, cycle: &Cycle, n: u64) -> T
+    where
+        T: Clone,
+        F: FnMut(&T) -> T,
+    {
+        let mut state = start.clone();
+        for _ in 0..cycle.reduce(n) {
+            state = f(&state);
+        }
+        state
+    }
+}
+
+// For comparing the detectors' memory use: install `Counting` as the global
+// allocator (as the benches do), and `peak` then reports the most memory
+// allocated at any one time during a call.
+pub mod measure {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    pub struct Counting;
+
+    static LIVE: AtomicUsize = AtomicUsize::new(0);
+    static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let live = LIVE.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(live, Ordering::SeqCst);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            LIVE.fetch_sub(layout.size(), Ordering::SeqCst);
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    // Not meaningful if other threads are allocating at the same time.
+    pub fn peak<T, F>(f: F) -> (T, usize)
+    where
+        F: FnOnce() -> T,
+    {
+        let before = LIVE.load(Ordering::SeqCst);
+        PEAK.store(before, Ordering::SeqCst);
+        let result = f();
+        (result, PEAK.load(Ordering::SeqCst) - before)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cycle::*;
+
+    // 0, 1, 2, 3, 4, 5, 6, 3, 4, 5, 6, ...
+    fn step(i: &u32) -> u32 {
+        if *i == 6 {
+            3
+        } else {
+            i + 1
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        let expected = Cycle { mu: 3, lambda: 4 };
+        assert_eq!(floyd(&0, step), expected);
+        assert_eq!(brent(&0, step), expected);
+        assert_eq!(hashing(&0, step), expected);
+        assert_eq!(brent(&5, step), Cycle { mu: 0, lambda: 4 });
+        assert_eq!(floyd(&7, |_| 7), Cycle { mu: 0, lambda: 1 });
+        assert_eq!(hashing(&9, |&i| if i > 0 { i - 1 } else { 0 }).mu, 9);
+    }
+
+    #[test]
+    fn test_nth_state() {
+        let cycle = brent(&0, step);
+        assert_eq!(cycle.reduce(2), 2);
+        assert_eq!(cycle.reduce(7), 3);
+        assert_eq!(nth_state(&0, step, &cycle, 6), 6);
+        assert_eq!(nth_state(&0, step, &cycle, 1_000_000_000_000), 4);
+    }
+}
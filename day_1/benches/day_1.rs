@@ -1,8 +1,8 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use day_1::day_1::{input, part_1, part_2};
+use day_1::day_1::{from_path, part_1, part_2};
 
 fn criterion_benchmark(c: &mut Criterion) {
-    let input = input();
+    let input = from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
     c.bench_function("day 1 part 1", |b| {
         b.iter(|| {
             part_1(&input);
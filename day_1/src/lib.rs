@@ -1,20 +1,47 @@
 pub mod day_1 {
+    use common::input::InputError;
+    use common::parse::ParseError;
+    use common::solution::Solution;
+    use std::io::Read;
+    use std::path::Path;
 
-    pub fn input() -> Vec<u32> {
-        let input = include_str!("../input.txt");
+    pub fn parse_input(input: &str) -> Result<Vec<u32>, ParseError> {
+        if input.trim().is_empty() {
+            return Err(ParseError::at_end(input, "a digit"));
+        }
+        let start = input.len() - input.trim_start().len();
         input
             .trim()
-            .chars()
-            .map(|l| {
-                l.to_digit(10)
-                    .unwrap_or_else(|| panic!("{} wasn't a valid u32", l))
+            .char_indices()
+            .map(|(i, c)| {
+                c.to_digit(10)
+                    .ok_or_else(|| ParseError::at_char(input, start + i, "a digit"))
             })
-            .collect::<Vec<u32>>()
+            .collect()
+    }
+
+    pub fn from_reader<R>(reader: R) -> Result<Vec<u32>, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<Vec<u32>, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
     }
 
     pub fn part_1(numbers: &[u32]) -> u32 {
         let mut sum = 0;
-        let mut previous = numbers[0];
+        let mut previous = match numbers.first() {
+            Some(&first) => first,
+            None => return 0,
+        };
         let len = numbers.len();
         for &item in numbers.iter().skip(1) {
             if item == previous {
@@ -45,18 +72,44 @@ pub mod day_1 {
 
         sum
     }
+
+    pub struct Day1;
+
+    impl Solution for Day1 {
+        type Input<'a> = Vec<u32>;
+        type Config = ();
+        type Answer1 = u32;
+        type Answer2 = u32;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_1(input)
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_2(input)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_1::*;
 
+    fn input() -> Vec<u32> {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn part1_known() {
         assert_eq!(part_1(&[1, 1, 2, 2]), 3);
         assert_eq!(part_1(&[1, 1, 1, 1]), 4);
         assert_eq!(part_1(&[1, 2, 3, 4]), 0);
         assert_eq!(part_1(&[9, 1, 2, 1, 2, 1, 2, 9]), 9);
+        assert_eq!(part_1(&[]), 0);
     }
 
     #[test]
@@ -68,6 +121,12 @@ mod tests {
         assert_eq!(part_2(&[1, 2, 1, 3, 1, 4, 1, 5]), 4);
     }
 
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(parse_input("12a3").unwrap_err().column, 3);
+        assert_eq!(parse_input(" \n").unwrap_err().expected, "a digit");
+    }
+
     #[test]
     fn test_day_1() {
         let input = input();
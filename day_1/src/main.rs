@@ -1,7 +1,10 @@
+use common::input::InputError;
 use day_1::day_1;
 
-fn main() {
-    let input = day_1::input();
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let input = day_1::parse_input(&input)?;
     println!("part 1 => {}", day_1::part_1(&input));
     println!("part 2 => {}", day_1::part_2(&input));
+    Ok(())
 }
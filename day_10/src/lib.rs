@@ -1,20 +1,32 @@
 pub mod day_10 {
+    use common::parse::ParseError;
+    use common::solution::Solution;
 
-    pub fn input_1() -> Vec<u8> {
-        let input = include_str!("../input.txt");
+    use crate::knot::{self, SALT};
+
+    pub fn parse_input_1(input: &str) -> Result<Vec<u8>, ParseError> {
+        let mut offset = input.len() - input.trim_start().len();
         input
             .trim()
             .split(',')
-            .map(|l| l.parse().unwrap())
-            .collect::<Vec<u8>>()
+            .map(|l| {
+                let start = offset;
+                offset += l.len() + 1;
+                l.parse()
+                    .map_err(|_| ParseError::at(input, start, Some(l), "a length from 0 to 255"))
+            })
+            .collect()
     }
 
-    pub fn input_2() -> Vec<u8> {
-        let input = include_str!("../input.txt");
-        input.trim().chars().map(|c| c as u8).collect::<Vec<u8>>()
+    pub fn parse_input_2(input: &str) -> Result<Vec<u8>, ParseError> {
+        let start = input.len() - input.trim_start().len();
+        match input.trim().find(|c: char| !c.is_ascii()) {
+            Some(i) => Err(ParseError::at_char(input, start + i, "an ASCII character")),
+            None => Ok(input.trim().bytes().collect()),
+        }
     }
 
-    fn rev<T>(start: usize, length: usize, b: &mut [T])
+    pub(crate) fn rev<T>(start: usize, length: usize, b: &mut [T])
     where
         T: Copy,
     {
@@ -72,15 +84,23 @@ pub mod day_10 {
     pub fn knot_hash_unsalted(bytes: &[u8]) -> Vec<u8> {
         let mut state = new_state(256);
         for _ in 0..64 {
-            execute_round(&mut state, &bytes);
+            execute_round(&mut state, bytes);
+        }
+        densify(&state.v)
+    }
+
+    // The straightforward implementation, which `knot::hash` is checked against.
+    pub fn knot_hash_reference(bytes: &[u8]) -> Vec<u8> {
+        let mut state = new_state(256);
+        for _ in 0..64 {
+            execute_round(&mut state, bytes);
+            execute_round(&mut state, &SALT);
         }
         densify(&state.v)
     }
 
     pub fn knot_hash(bytes: &[u8]) -> Vec<u8> {
-        let mut copy: Vec<u8> = bytes.to_vec();
-        copy.extend(vec![17, 31, 73, 47, 23]);
-        knot_hash_unsalted(&copy)
+        knot::hash(bytes).to_vec()
     }
 
     pub fn to_hex_str(bytes: &[u8]) -> String {
@@ -95,11 +115,226 @@ pub mod day_10 {
     pub fn part_2(input: &[u8]) -> String {
         to_hex_str(&knot_hash(input))
     }
+
+    pub struct Input {
+        pub lengths: Vec<u8>,
+        pub bytes: Vec<u8>,
+    }
+
+    pub struct Config {
+        pub size: usize,
+    }
+
+    impl Default for Config {
+        fn default() -> Config {
+            Config { size: 256 }
+        }
+    }
+
+    pub struct Day10;
+
+    impl Solution for Day10 {
+        type Input<'a> = Input;
+        type Config = Config;
+        type Answer1 = u32;
+        type Answer2 = String;
+
+        fn parse(input: &str) -> Result<Input, ParseError> {
+            Ok(Input {
+                lengths: parse_input_1(input)?,
+                bytes: parse_input_2(input)?,
+            })
+        }
+
+        fn part_1(input: &Input, config: &Config) -> u32 {
+            part_1(config.size, &input.lengths)
+        }
+
+        fn part_2(input: &Input, _: &Config) -> String {
+            part_2(&input.bytes)
+        }
+    }
+}
+
+pub mod knot {
+    use crate::day_10::rev;
+    use std::hash::Hasher;
+
+    pub const SALT: [u8; 5] = [17, 31, 73, 47, 23];
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Params {
+        pub size: usize,
+        pub rounds: usize,
+        pub salt: Vec<u8>,
+    }
+
+    impl Default for Params {
+        fn default() -> Params {
+            Params {
+                size: 256,
+                rounds: 64,
+                salt: SALT.to_vec(),
+            }
+        }
+    }
+
+    impl Params {
+        // The dense hash XORs the ring down to 16 bytes, so the ring has to
+        // split evenly into 16 blocks, and its marks have to fit in a byte.
+        pub fn is_valid(&self) -> bool {
+            self.size > 0 && self.size.is_multiple_of(16) && self.size <= 256
+        }
+    }
+
+    // The ring, stored so that a reversal never has to wrap: whenever one
+    // would run off the end, the ring is rotated to bring the current
+    // position to the front first. Then every reversal is a contiguous
+    // `slice::reverse`, with no modular arithmetic per element. The marks are
+    // bytes, so there can be at most 256 of them and the ring fits on the stack.
+    struct Ring {
+        marks: [u8; 256],
+        size: usize,
+        pos: usize,
+        // How far the ring has been rotated from its true start.
+        offset: usize,
+        skip: usize,
+    }
+
+    impl Ring {
+        fn new(size: usize) -> Ring {
+            let mut marks = [0u8; 256];
+            for (i, mark) in marks.iter_mut().enumerate() {
+                *mark = i as u8;
+            }
+            Ring {
+                marks,
+                size,
+                pos: 0,
+                offset: 0,
+                skip: 0,
+            }
+        }
+
+        fn round(&mut self, lengths: &[u8]) {
+            let size = self.size;
+            let marks = &mut self.marks[..size];
+            for &length in lengths {
+                let length = length as usize;
+                if length > size {
+                    // Not a valid length, but reverse it the way the
+                    // original does so that the two always agree.
+                    rev(self.pos, length, marks);
+                } else {
+                    if self.pos + length > size {
+                        marks.rotate_left(self.pos);
+                        self.offset = (self.offset + self.pos) % size;
+                        self.pos = 0;
+                    }
+                    marks[self.pos..self.pos + length].reverse();
+                }
+                self.pos = (self.pos + length + self.skip) % size;
+                self.skip += 1;
+            }
+        }
+
+        fn dense(mut self) -> [u8; 16] {
+            let marks = &mut self.marks[..self.size];
+            marks.rotate_right(self.offset);
+            let mut answer = [0u8; 16];
+            for (out, chunk) in answer.iter_mut().zip(marks.chunks_exact(self.size / 16)) {
+                *out = chunk.iter().fold(0, |x, y| x ^ y);
+            }
+            answer
+        }
+    }
+
+    // Every round reads the whole input, so the hasher can only buffer what
+    // it's given and do the work when asked for the digest.
+    #[derive(Clone, Debug, Default)]
+    pub struct KnotHasher {
+        params: Params,
+        buffer: Vec<u8>,
+    }
+
+    impl KnotHasher {
+        pub fn new() -> KnotHasher {
+            KnotHasher::default()
+        }
+
+        pub fn with_params(params: Params) -> Result<KnotHasher, String> {
+            if !params.is_valid() {
+                return Err(format!(
+                    "Ring size must be a multiple of 16 from 16 to 256, got {}",
+                    params.size
+                ));
+            }
+            Ok(KnotHasher {
+                params,
+                buffer: vec![],
+            })
+        }
+
+        pub fn params(&self) -> &Params {
+            &self.params
+        }
+
+        pub fn update(&mut self, bytes: &[u8]) {
+            self.buffer.extend_from_slice(bytes);
+        }
+
+        pub fn digest(&self) -> [u8; 16] {
+            let mut ring = Ring::new(self.params.size);
+            for _ in 0..self.params.rounds {
+                ring.round(&self.buffer);
+                ring.round(&self.params.salt);
+            }
+            ring.dense()
+        }
+
+        pub fn finalize(self) -> [u8; 16] {
+            self.digest()
+        }
+
+        pub fn reset(&mut self) {
+            self.buffer.clear();
+        }
+    }
+
+    impl Hasher for KnotHasher {
+        fn write(&mut self, bytes: &[u8]) {
+            self.update(bytes);
+        }
+
+        fn finish(&self) -> u64 {
+            let digest = self.digest();
+            let mut first = [0u8; 8];
+            first.copy_from_slice(&digest[..8]);
+            u64::from_le_bytes(first)
+        }
+    }
+
+    pub fn hash(bytes: &[u8]) -> [u8; 16] {
+        let mut ring = Ring::new(256);
+        for _ in 0..64 {
+            ring.round(bytes);
+            ring.round(&SALT);
+        }
+        ring.dense()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_10::*;
+    use super::knot::{self, KnotHasher, Params};
+    use common::solution::Solution;
+    use std::collections::HashMap;
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    fn raw_input() -> String {
+        common::input::from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
 
     #[test]
     fn part1_known() {
@@ -110,8 +345,25 @@ mod tests {
         assert_eq!(state.curr_pos, 4);
     }
 
+    #[test]
+    fn part1_known_solution() {
+        let input = Day10::parse("3,4,1,5").unwrap();
+        assert_eq!(Day10::part_1(&input, &Config { size: 5 }), 12);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse_input_1("3,4,256,5").unwrap_err();
+        assert_eq!(error.column, 5);
+        assert_eq!(error.token.as_deref(), Some("256"));
+    }
+
     #[test]
     fn part2_known() {
+        assert_eq!(
+            to_hex_str(&knot_hash_reference("".as_bytes())),
+            "a2582a3a0e66e6e86e3812dcb672a272"
+        );
         assert_eq!(
             to_hex_str(&knot_hash("".as_bytes())),
             "a2582a3a0e66e6e86e3812dcb672a272"
@@ -130,11 +382,120 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_knot_hasher() {
+        for input in &["", "AoC 2017", "1,2,3", "1,2,4"] {
+            let expected = knot_hash(input.as_bytes());
+            assert_eq!(knot::hash(input.as_bytes()).to_vec(), expected);
+
+            let mut hasher = KnotHasher::new();
+            for chunk in input.as_bytes().chunks(3) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize().to_vec(), expected);
+        }
+    }
+
+    #[test]
+    fn test_knot_hash_matches_reference() {
+        // A cheap generator, so the inputs are the same every run.
+        let mut seed = 12345u32;
+        let mut next = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as u8
+        };
+        for len in 0..64 {
+            let input: Vec<u8> = (0..len).map(|_| next()).collect();
+            assert_eq!(knot_hash(&input), knot_hash_reference(&input));
+        }
+        let key: Vec<u8> = b"flqrgnkx-127".to_vec();
+        assert_eq!(knot_hash(&key), knot_hash_reference(&key));
+    }
+
+    #[test]
+    fn test_knot_hasher_params() {
+        let unsalted = Params {
+            salt: vec![],
+            ..Params::default()
+        };
+        let mut hasher = KnotHasher::with_params(unsalted).unwrap();
+        hasher.update(b"3,4,1,5");
+        assert_eq!(hasher.digest().to_vec(), knot_hash_unsalted(b"3,4,1,5"));
+
+        let small = Params {
+            size: 16,
+            rounds: 1,
+            salt: vec![],
+        };
+        let mut hasher = KnotHasher::with_params(small).unwrap();
+        hasher.update(&[3, 4, 1, 5]);
+        // One byte per block, so the dense hash is just the ring.
+        let mut state = new_state(16);
+        execute_round(&mut state, &[3, 4, 1, 5]);
+        assert_eq!(hasher.finalize().to_vec(), state.v);
+
+        // Lengths longer than the ring still agree with the original.
+        for &size in &[16, 32, 48, 128] {
+            let params = Params {
+                size,
+                rounds: 3,
+                ..Params::default()
+            };
+            let mut hasher = KnotHasher::with_params(params).unwrap();
+            hasher.update(b"AoC 2017");
+            let mut state = new_state(size);
+            for _ in 0..3 {
+                execute_round(&mut state, b"AoC 2017");
+                execute_round(&mut state, &knot::SALT);
+            }
+            let expected: Vec<u8> = state
+                .v
+                .chunks_exact(size / 16)
+                .map(|c| c.iter().fold(0, |x, y| x ^ y))
+                .collect();
+            assert_eq!(hasher.finalize().to_vec(), expected);
+        }
+    }
+
+    #[test]
+    fn test_knot_hasher_bad_size() {
+        for &size in &[0, 100, 272] {
+            let error = KnotHasher::with_params(Params {
+                size,
+                ..Params::default()
+            })
+            .unwrap_err();
+            assert_eq!(
+                error,
+                format!(
+                    "Ring size must be a multiple of 16 from 16 to 256, got {}",
+                    size
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_knot_hash_map() {
+        let mut map: HashMap<&str, usize, BuildHasherDefault<KnotHasher>> = HashMap::default();
+        for (i, key) in ["flqrgnkx", "AoC 2017", ""].iter().enumerate() {
+            map.insert(key, i);
+        }
+        assert_eq!(map.get("AoC 2017"), Some(&1));
+        assert_eq!(map.get(""), Some(&2));
+        assert_eq!(map.get("nope"), None);
+
+        let mut a = KnotHasher::new();
+        a.write(b"AoC 2017");
+        assert_eq!(a.finish().to_le_bytes()[..], knot::hash(b"AoC 2017")[..8]);
+    }
+
     #[test]
     fn test_day_10() {
-        let input = input_1();
+        let raw = raw_input();
+        let input = parse_input_1(&raw).unwrap();
         assert_eq!(part_1(256, &input), 4114);
-        let input = input_2();
+        let input = parse_input_2(&raw).unwrap();
         assert_eq!(part_2(&input), "2f8c3d2100fdd57cec130d928b0fd2dd");
     }
 }
@@ -1,10 +1,15 @@
+use common::input::InputError;
 use day_10::day_10;
 
-fn main() {
-    let input = day_10::input_1();
-    println!("part 1 => {}", day_10::part_1(256, &input));
-
-    let mut input = day_10::input_2();
-    input.extend(vec![17, 31, 73, 47, 23]);
-    println!("part 2 => {}", day_10::part_2(&input));
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    println!(
+        "part 1 => {}",
+        day_10::part_1(256, &day_10::parse_input_1(&input)?)
+    );
+    println!(
+        "part 2 => {}",
+        day_10::part_2(&day_10::parse_input_2(&input)?)
+    );
+    Ok(())
 }
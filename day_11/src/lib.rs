@@ -1,4 +1,9 @@
 pub mod day_11 {
+    use common::input::InputError;
+    use common::parse::ParseError;
+    use common::solution::Solution;
+    use std::io::Read;
+    use std::path::Path;
     pub enum Direction {
         South,
         North,
@@ -8,38 +13,45 @@ pub mod day_11 {
         SouthWest,
     }
 
-    fn parse(s: &str) -> Direction {
-        let mut chars = s.chars();
-        let c1 = chars.next().unwrap();
-        match chars.next() {
-            None => match c1 {
-                's' => Direction::South,
-                'n' => Direction::North,
-                c1 => panic!("Expected south or north, got: {}", c1),
-            },
-            Some(c2) => match c1 {
-                's' => match c2 {
-                    'e' => Direction::SouthEast,
-                    'w' => Direction::SouthWest,
-                    c2 => panic!("Expected SE or SW, got s{}", c2),
-                },
-                'n' => match c2 {
-                    'e' => Direction::NorthEast,
-                    'w' => Direction::NorthWest,
-                    c2 => panic!("Expected NE or NW, got n{}", c2),
-                },
-                c1 => panic!("Expected north or south for first direction, got: {}", c1),
-            },
+    fn parse(s: &str) -> Option<Direction> {
+        match s {
+            "s" => Some(Direction::South),
+            "n" => Some(Direction::North),
+            "se" => Some(Direction::SouthEast),
+            "sw" => Some(Direction::SouthWest),
+            "ne" => Some(Direction::NorthEast),
+            "nw" => Some(Direction::NorthWest),
+            _ => None,
         }
     }
 
-    pub fn input() -> Vec<Direction> {
-        let input = include_str!("../input.txt");
+    pub fn parse_input(input: &str) -> Result<Vec<Direction>, ParseError> {
+        let mut offset = input.len() - input.trim_start().len();
         input
             .trim()
             .split(',')
-            .map(parse)
-            .collect::<Vec<Direction>>()
+            .map(|d| {
+                let start = offset;
+                offset += d.len() + 1;
+                parse(d).ok_or_else(|| ParseError::at(input, start, Some(d), "a direction"))
+            })
+            .collect()
+    }
+
+    pub fn from_reader<R>(reader: R) -> Result<Vec<Direction>, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<Vec<Direction>, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
     }
 
     fn abs(i: i32) -> u32 {
@@ -83,12 +95,37 @@ pub mod day_11 {
         });
         best
     }
+
+    pub struct Day11;
+
+    impl Solution for Day11 {
+        type Input<'a> = Vec<Direction>;
+        type Config = ();
+        type Answer1 = u32;
+        type Answer2 = u32;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_1(input)
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_2(input)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_11::*;
 
+    fn input() -> Vec<Direction> {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn part1_known() {
         assert_eq!(
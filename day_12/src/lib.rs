@@ -1,85 +1,61 @@
 pub mod day_12 {
-    use std::collections::HashMap;
-    use std::collections::HashSet;
-    use std::hash::Hash;
+    use common::input::InputError;
+    use common::parse::ParseError;
+    use common::solution::{MaybeAnswer, Solution};
+    use std::io::Read;
+    use std::path::Path;
 
-    pub struct Node {
-        number: u32,
-        friends: Vec<u32>,
-    }
-
-    pub fn parse(s: &str) -> Node {
-        let mut iter = s.split_whitespace();
-        let number: u32 = iter.next().unwrap().parse().unwrap();
-        match iter.next().unwrap() {
-            "<->" => {}
-            s => panic!("Bad! {}", s),
-        }
+    pub use graph::graph::Graph;
 
-        let friends = iter
-            .map(|s| {
-                let mut ans = 0;
-                for d in s.chars() {
-                    match d.to_digit(10) {
-                        None => break,
-                        Some(d) => ans = ans * 10 + d,
-                    }
-                }
-                ans
-            })
-            .collect::<Vec<_>>();
-
-        Node { number, friends }
+    pub fn parse_input(input: &str) -> Result<Graph, ParseError> {
+        graph::graph::parse(input)
     }
 
-    pub fn input() -> Vec<Node> {
-        let input = include_str!("../input.txt");
-        input.lines().map(|l| parse(l)).collect::<Vec<_>>()
+    pub fn from_reader<R>(reader: R) -> Result<Graph, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
     }
 
-    fn connected_component<T>(relations: &HashMap<T, &[T]>, component: T) -> HashSet<T>
+    pub fn from_path<P>(path: P) -> Result<Graph, InputError>
     where
-        T: Eq + Hash + Clone,
+        P: AsRef<Path>,
     {
-        let mut stack: Vec<_> = relations.get(&component).unwrap().to_vec();
-        let mut connected: HashSet<_> = HashSet::new();
-        connected.insert(component);
-
-        while let Some(explore) = stack.pop() {
-            if connected.insert(explore.clone()) {
-                let relatives = relations.get(&explore).unwrap().iter();
-                stack.extend(relatives.filter(|i| !connected.contains(&i)).cloned());
-            }
-        }
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
+    }
 
-        connected
+    // `None` if there's no program 0 in the village.
+    pub fn part_1(graph: &Graph) -> Option<usize> {
+        let program = graph.index("0")?;
+        Some(graph.components().size_of(program))
     }
 
-    pub fn part_1(input: &[Node]) -> usize {
-        let relations: HashMap<u32, &[u32]> = input
-            .iter()
-            .map(|n| (n.number, n.friends.as_slice()))
-            .collect();
-        connected_component(&relations, 0).len()
+    pub fn part_2(graph: &Graph) -> usize {
+        graph.components().count()
     }
 
-    pub fn part_2(input: &[Node]) -> u32 {
-        let relations: HashMap<u32, &[u32]> = input
-            .iter()
-            .map(|n| (n.number, n.friends.as_slice()))
-            .collect();
-
-        let mut count = 0;
-        let mut components_found = HashSet::new();
-        for node in input {
-            if !components_found.contains(&node.number) {
-                let component = connected_component(&relations, node.number);
-                components_found.extend(component);
-                count += 1;
-            }
+    pub struct Day12;
+
+    impl Solution for Day12 {
+        type Input<'a> = Graph;
+        type Config = ();
+        type Answer1 = MaybeAnswer<usize>;
+        type Answer2 = usize;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> MaybeAnswer<usize> {
+            MaybeAnswer(part_1(input))
         }
 
-        count
+        fn part_2(input: &Self::Input<'_>, _: &()) -> usize {
+            part_2(input)
+        }
     }
 }
 
@@ -87,24 +63,31 @@ pub mod day_12 {
 mod tests {
     use super::day_12::*;
 
-    fn data() -> Vec<Node> {
-        vec![
-            "0 <-> 2",
-            "1 <-> 1",
-            "2 <-> 0, 3, 4",
-            "3 <-> 2, 4",
-            "4 <-> 2, 3, 6",
-            "5 <-> 6",
-            "6 <-> 4, 5",
-        ]
-        .iter()
-        .map(|s| parse(s))
-        .collect()
+    fn input() -> Graph {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
+    fn data() -> Graph {
+        parse_input(
+            &[
+                "0 <-> 2",
+                "1 <-> 1",
+                "2 <-> 0, 3, 4",
+                "3 <-> 2, 4",
+                "4 <-> 2, 3, 6",
+                "5 <-> 6",
+                "6 <-> 4, 5",
+            ]
+            .join("\n"),
+        )
+        .unwrap()
     }
 
     #[test]
     fn part1_known() {
-        assert_eq!(part_1(&data()), 6);
+        assert_eq!(part_1(&data()), Some(6));
+        assert_eq!(part_1(&parse_input("1 <-> 2").unwrap()), None);
+        assert_eq!(part_1(&parse_input("").unwrap()), None);
     }
 
     #[test]
@@ -115,7 +98,7 @@ mod tests {
     #[test]
     fn test_day_12() {
         let input = input();
-        assert_eq!(part_1(&input), 378);
+        assert_eq!(part_1(&input), Some(378));
         assert_eq!(part_2(&input), 204);
     }
 }
@@ -1,7 +1,32 @@
+use common::input::InputError;
+use common::solution::MaybeAnswer;
 use day_12::day_12;
+use std::process;
 
-fn main() {
-    let input = day_12::input();
-    println!("part 1 => {}", day_12::part_1(&input));
-    println!("part 2 => {}", day_12::part_2(&input));
+const USAGE: &str = "Usage: day_12 [FILE] [dot|graphml]";
+
+fn main() -> Result<(), InputError> {
+    // Optionally `dot` or `graphml` after the input file, to draw the
+    // village with each group of programs in its own colour.
+    let format = std::env::args().nth(2);
+    if let Some(format) = format.as_deref() {
+        if format != "dot" && format != "graphml" {
+            eprintln!(
+                "day_12: Expected dot or graphml, got: {}\n{}",
+                format, USAGE
+            );
+            process::exit(2);
+        }
+    }
+    let input = common::input::from_args()?;
+    let input = day_12::parse_input(&input)?;
+    match format.as_deref() {
+        Some("dot") => print!("{}", input.to_document("village").to_dot()),
+        Some(_) => print!("{}", input.to_document("village").to_graphml()),
+        None => {
+            println!("part 1 => {}", MaybeAnswer(day_12::part_1(&input)));
+            println!("part 2 => {}", day_12::part_2(&input));
+        }
+    }
+    Ok(())
 }
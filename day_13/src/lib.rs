@@ -1,32 +1,51 @@
 pub mod day_13 {
+    use common::input::InputError;
+    use common::parse::{self, ParseError, Tokens};
+    use common::solution::Solution;
+    use std::io::Read;
+    use std::path::Path;
 
+    #[derive(Debug)]
     pub struct Layer {
         depth: u8,
         range: u8,
     }
 
-    pub fn parse(s: &str) -> Layer {
-        let mut chars = s.chars();
-        let mut depth = 0u8;
-        while let Some(i) = chars.next().unwrap().to_digit(10) {
-            depth = depth * 10 + i as u8;
-        }
-        match chars.next() {
-            Some(' ') => {}
-            x => {
-                panic!("Expected space, got: {:?}", x);
-            }
-        }
-        let mut range = 0u8;
-        for s in chars {
-            range = range * 10 + s.to_digit(10).unwrap() as u8;
-        }
-        Layer { depth, range }
+    pub fn parse(s: &str) -> Result<Layer, ParseError> {
+        let mut tokens = Tokens::new(s);
+        let token = tokens.token("a depth followed by ':'")?;
+        let depth = token
+            .text
+            .strip_suffix(':')
+            .and_then(|d| d.parse().ok())
+            .ok_or_else(|| tokens.error(&token, "a depth followed by ':'"))?;
+        let token = tokens.token("a range")?;
+        let range = match token.text.parse() {
+            Ok(range) if range > 0 => range,
+            _ => return Err(tokens.error(&token, "a positive range")),
+        };
+        tokens.end()?;
+        Ok(Layer { depth, range })
+    }
+
+    pub fn parse_input(input: &str) -> Result<Vec<Layer>, ParseError> {
+        parse::lines(input, parse)
     }
 
-    pub fn input() -> Vec<Layer> {
-        let input = include_str!("../input.txt");
-        input.lines().map(parse).collect::<Vec<_>>()
+    pub fn from_reader<R>(reader: R) -> Result<Vec<Layer>, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<Vec<Layer>, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
     }
 
     pub(crate) fn pos_at_time(range: u8, t: u8) -> u8 {
@@ -86,12 +105,37 @@ pub mod day_13 {
             })
             .unwrap()
     }
+
+    pub struct Day13;
+
+    impl Solution for Day13 {
+        type Input<'a> = Vec<Layer>;
+        type Config = ();
+        type Answer1 = u32;
+        type Answer2 = u32;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_1(input)
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_2(input)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_13::*;
 
+    fn input() -> Vec<Layer> {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn test_pos_at_time() {
         for (time, count) in [0, 1, 2, 3, 2, 1, 0, 1, 2, 3, 2, 1, 0, 1, 2, 3]
@@ -104,22 +148,31 @@ mod tests {
 
     #[test]
     fn part1_known() {
-        let data: Vec<Layer> = vec!["0: 3", "1: 2", "4: 4", "6: 4"]
+        let data: Vec<Layer> = ["0: 3", "1: 2", "4: 4", "6: 4"]
             .iter()
-            .map(|i| parse(i))
+            .map(|i| parse(i).unwrap())
             .collect();
         assert_eq!(part_1(&data), 24);
     }
 
     #[test]
     fn part2_known() {
-        let data: Vec<Layer> = vec!["0: 3", "1: 2", "4: 4", "6: 4"]
+        let data: Vec<Layer> = ["0: 3", "1: 2", "4: 4", "6: 4"]
             .iter()
-            .map(|i| parse(i))
+            .map(|i| parse(i).unwrap())
             .collect();
         assert_eq!(part_2(&data), 10);
     }
 
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(
+            parse("0 3").unwrap_err().expected,
+            "a depth followed by ':'"
+        );
+        assert_eq!(parse("0: 0").unwrap_err().column, 4);
+    }
+
     #[test]
     fn test_day_13() {
         let input = input();
@@ -1,7 +1,10 @@
+use common::input::InputError;
 use day_13::day_13;
 
-fn main() {
-    let layers = day_13::input();
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let layers = day_13::parse_input(&input)?;
     println!("part 1 => {}", day_13::part_1(&layers));
     println!("part 2 => {}", day_13::part_2(&layers));
+    Ok(())
 }
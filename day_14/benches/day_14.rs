@@ -1,21 +1,40 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use day_14::day_14::{input, part_1, part_1_longhand, part_2};
+use day_10::day_10::knot_hash_reference;
+use day_10::knot;
+use day_14::day_14::{parse_input, part_1, part_1_longhand, part_2};
 
 fn criterion_benchmark(c: &mut Criterion) {
-    let input = input();
+    let input =
+        common::input::from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
+    let input = parse_input(&input).unwrap();
+    let rows: Vec<String> = (0..128).map(|row| format!("{}-{}", input, row)).collect();
+    c.bench_function("day 14 rows reference", |b| {
+        b.iter(|| {
+            for row in &rows {
+                knot_hash_reference(row.as_bytes());
+            }
+        })
+    });
+    c.bench_function("day 14 rows fast", |b| {
+        b.iter(|| {
+            for row in &rows {
+                knot::hash(row.as_bytes());
+            }
+        })
+    });
     c.bench_function("day 14 part 1", |b| {
         b.iter(|| {
-            part_1(&input);
+            part_1(input);
         })
     });
     c.bench_function("day 14 part 1 longhand", |b| {
         b.iter(|| {
-            part_1_longhand(&input);
+            part_1_longhand(input);
         })
     });
     c.bench_function("day 14 part 2", |b| {
         b.iter(|| {
-            part_2(&input);
+            part_2(input);
         })
     });
 }
@@ -1,9 +1,12 @@
 pub mod day_14 {
+    use common::parse::ParseError;
+    use common::solution::Solution;
+    use std::fmt;
 
-    use day_10::day_10::knot_hash;
+    use day_10::knot::hash as knot_hash;
 
-    pub fn input() -> &'static str {
-        include_str!("../input.txt").trim()
+    pub fn parse_input(input: &str) -> Result<&str, ParseError> {
+        Ok(input.trim())
     }
 
     fn count_ones(i: u8) -> u8 {
@@ -20,7 +23,7 @@ pub mod day_14 {
 
     pub fn part_1_longhand(key: &str) -> u32 {
         let mut buffer: Vec<u8> = key.chars().map(|i| i as u8).collect();
-        buffer.extend(&[b'-', b'0']);
+        buffer.extend(b"-0");
         let len = buffer.len();
 
         let mut count = 0u32;
@@ -65,143 +68,397 @@ pub mod day_14 {
         count
     }
 
-    fn value_at<T>(i: &[T], width: usize, row: usize, col: usize) -> &T {
-        &i[row * width + col]
+    fn row_hash(key: &str, row: usize) -> u128 {
+        u128::from_be_bytes(knot_hash(format!("{}-{}", key, row).as_bytes()))
     }
 
-    fn set_value_at<T>(i: &mut [T], width: usize, row: usize, col: usize, value: T) {
-        i[row * width + col] = value;
+    #[cfg(not(feature = "parallel"))]
+    fn row_hashes(key: &str, height: usize) -> Vec<u128> {
+        (0..height).map(|row| row_hash(key, row)).collect()
     }
 
-    fn flood_fill(i: &mut [u32], width: usize, value: u32, row: usize, col: usize) {
-        let current = *value_at(i, width, row, col);
-        if current != 1 {
-            return;
+    // The rows are independent, and an indexed parallel iterator collects
+    // them back in order, so the grid is the same either way.
+    #[cfg(feature = "parallel")]
+    fn row_hashes(key: &str, height: usize) -> Vec<u128> {
+        use rayon::prelude::*;
+        (0..height)
+            .into_par_iter()
+            .map(|row| row_hash(key, row))
+            .collect()
+    }
+
+    // One row of the disk per knot hash of "key-row", each stored as a
+    // single 128-bit number whose most significant bit is the leftmost square.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct KnotGrid {
+        rows: Vec<u128>,
+    }
+
+    impl KnotGrid {
+        pub const WIDTH: usize = 128;
+
+        pub fn new(key: &str, height: usize) -> KnotGrid {
+            KnotGrid {
+                rows: row_hashes(key, height),
+            }
+        }
+
+        pub fn from_rows(rows: Vec<u128>) -> KnotGrid {
+            KnotGrid { rows }
+        }
+
+        pub fn height(&self) -> usize {
+            self.rows.len()
+        }
+
+        pub fn width(&self) -> usize {
+            KnotGrid::WIDTH
         }
-        set_value_at(i, width, row, col, value);
-        if row < (i.len() / width) - 1 {
-            flood_fill(i, width, value, row + 1, col);
+
+        pub fn row(&self, row: usize) -> u128 {
+            self.rows[row]
         }
-        if row > 0 {
-            flood_fill(i, width, value, row - 1, col);
+
+        pub fn rows(&self) -> &[u128] {
+            &self.rows
         }
-        if col > 0 {
-            flood_fill(i, width, value, row, col - 1);
+
+        pub fn row_bytes(&self, row: usize) -> [u8; 16] {
+            self.rows[row].to_be_bytes()
         }
-        if col < width - 1 {
-            flood_fill(i, width, value, row, col + 1);
+
+        pub fn is_used(&self, row: usize, col: usize) -> bool {
+            assert!(col < KnotGrid::WIDTH, "Column {} out of range", col);
+            (self.rows[row] >> (KnotGrid::WIDTH - 1 - col)) & 1 == 1
+        }
+
+        pub fn count_used(&self) -> usize {
+            self.rows.iter().map(|r| r.count_ones() as usize).sum()
+        }
+
+        // The (row, column) of every used square, row by row, left to right.
+        pub fn used(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+            self.rows.iter().enumerate().flat_map(|(row, &bits)| {
+                (0..KnotGrid::WIDTH)
+                    .filter(move |col| (bits >> (KnotGrid::WIDTH - 1 - col)) & 1 == 1)
+                    .map(move |col| (row, col))
+            })
         }
     }
 
-    fn count_contiguous(mut i: Vec<u32>, width: usize) -> u32 {
-        // 0 means empty, 1 means "full and not assigned a group".
-        let height = i.len() / width;
-        let mut next_group_number = 2;
-        for row in 0..height {
-            for col in 0..width {
-                if *value_at(&i, width, row, col) == 1 {
-                    flood_fill(&mut i, width, next_group_number, row, col);
-                    next_group_number += 1;
+    impl fmt::Display for KnotGrid {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            for (row, &bits) in self.rows.iter().enumerate() {
+                if row > 0 {
+                    writeln!(f)?;
+                }
+                for col in (0..KnotGrid::WIDTH).rev() {
+                    write!(f, "{}", if (bits >> col) & 1 == 1 { '#' } else { '.' })?;
                 }
             }
+            Ok(())
         }
-        next_group_number - 2
     }
 
-    pub(crate) fn render_vec(key: &str) -> Vec<u32> {
-        let mut output: Vec<u32> = vec![0; 128 * 128];
-        let mut buffer: Vec<u8> = key.chars().map(|i| i as u8).collect();
-        buffer.extend(&[b'-', b'0']);
-        let len = buffer.len();
-
-        let mut row = 0;
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Connectivity {
+        // Squares touch only along an edge, as in the puzzle.
+        Four,
+        // Squares touching at a corner are also adjacent.
+        Eight,
+    }
 
-        for i in 0u8..=9 {
-            buffer[len - 1] = i + b'0';
-            let hash = knot_hash(&buffer);
-            let mut col = 0;
-            for byte in hash {
-                let mut byte = byte;
-                for i in (0..8).rev() {
-                    set_value_at(&mut output, 128, row, col + i, (byte % 2) as u32);
-                    byte /= 2;
-                }
-                col += 8;
+    impl Connectivity {
+        fn offsets(self) -> &'static [(isize, isize)] {
+            match self {
+                Connectivity::Four => &[(-1, 0), (0, -1), (0, 1), (1, 0)],
+                Connectivity::Eight => &[
+                    (-1, -1),
+                    (-1, 0),
+                    (-1, 1),
+                    (0, -1),
+                    (0, 1),
+                    (1, -1),
+                    (1, 0),
+                    (1, 1),
+                ],
             }
-            row += 1;
         }
+    }
 
-        buffer.push(0);
-        for i in 1u8..=9 {
-            buffer[len - 1] = i + b'0';
-            for j in 0u8..=9 {
-                buffer[len] = j + b'0';
-                let hash = knot_hash(&buffer);
-                let mut col = 0;
-                for byte in hash {
-                    let mut byte = byte;
-                    for i in (0..8).rev() {
-                        set_value_at(&mut output, 128, row, col + i, (byte % 2) as u32);
-                        byte /= 2;
+    // Inclusive at both ends.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct BoundingBox {
+        pub top: usize,
+        pub left: usize,
+        pub bottom: usize,
+        pub right: usize,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Region {
+        pub size: usize,
+        pub bounds: BoundingBox,
+    }
+
+    // Every used square labelled with the region it's in. Regions are
+    // numbered in the order their first square appears, reading row by row.
+    pub struct Regions {
+        width: usize,
+        labels: Vec<Option<usize>>,
+        regions: Vec<Region>,
+    }
+
+    impl Regions {
+        // Flood fills with an explicit stack rather than by recursion, so a
+        // region can be as large as memory allows.
+        pub fn new<F>(
+            width: usize,
+            height: usize,
+            connectivity: Connectivity,
+            is_used: F,
+        ) -> Regions
+        where
+            F: Fn(usize, usize) -> bool,
+        {
+            let mut labels = vec![None; width * height];
+            let mut regions = vec![];
+            let mut stack = vec![];
+            for row in 0..height {
+                for col in 0..width {
+                    if labels[row * width + col].is_some() || !is_used(row, col) {
+                        continue;
+                    }
+                    let label = regions.len();
+                    let mut region = Region {
+                        size: 0,
+                        bounds: BoundingBox {
+                            top: row,
+                            left: col,
+                            bottom: row,
+                            right: col,
+                        },
+                    };
+                    labels[row * width + col] = Some(label);
+                    stack.push((row, col));
+                    while let Some((r, c)) = stack.pop() {
+                        region.size += 1;
+                        let bounds = &mut region.bounds;
+                        bounds.top = bounds.top.min(r);
+                        bounds.bottom = bounds.bottom.max(r);
+                        bounds.left = bounds.left.min(c);
+                        bounds.right = bounds.right.max(c);
+                        for &(dr, dc) in connectivity.offsets() {
+                            let (r, c) = (r.wrapping_add(dr as usize), c.wrapping_add(dc as usize));
+                            if r < height
+                                && c < width
+                                && labels[r * width + c].is_none()
+                                && is_used(r, c)
+                            {
+                                labels[r * width + c] = Some(label);
+                                stack.push((r, c));
+                            }
+                        }
                     }
-                    col += 8;
+                    regions.push(region);
                 }
-                row += 1;
+            }
+            Regions {
+                width,
+                labels,
+                regions,
             }
         }
 
-        buffer.push(0);
-        buffer[len - 1] = b'1';
-        for i in 0u8..=2 {
-            buffer[len] = i + b'0';
-            for j in 0u8..=(if i == 2 { 7 } else { 9 }) {
-                buffer[len + 1] = j + b'0';
-                let hash = knot_hash(&buffer);
-                let mut col = 0;
-                for byte in hash {
-                    let mut byte = byte;
-                    for i in (0..8).rev() {
-                        set_value_at(&mut output, 128, row, col + i, (byte % 2) as u32);
-                        byte /= 2;
-                    }
-                    col += 8;
-                }
-                row += 1;
-            }
+        pub fn count(&self) -> usize {
+            self.regions.len()
+        }
+
+        pub fn regions(&self) -> &[Region] {
+            &self.regions
+        }
+
+        // One label per square, row by row; `None` for a free square.
+        pub fn labels(&self) -> &[Option<usize>] {
+            &self.labels
+        }
+
+        pub fn label(&self, row: usize, col: usize) -> Option<usize> {
+            assert!(col < self.width, "Column {} out of range", col);
+            self.labels[row * self.width + col]
         }
 
-        output
+        pub fn region_at(&self, row: usize, col: usize) -> Option<&Region> {
+            self.label(row, col).map(|label| &self.regions[label])
+        }
+    }
+
+    impl KnotGrid {
+        pub fn regions(&self, connectivity: Connectivity) -> Regions {
+            Regions::new(self.width(), self.height(), connectivity, |row, col| {
+                self.is_used(row, col)
+            })
+        }
     }
 
     pub fn part_1(key: &str) -> usize {
-        render_vec(key).iter().cloned().filter(|i| *i == 1).count()
+        KnotGrid::new(key, 128).count_used()
     }
 
-    pub fn part_2(key: &str) -> u32 {
-        count_contiguous(render_vec(key), 128)
+    pub fn part_2(key: &str) -> usize {
+        KnotGrid::new(key, 128).regions(Connectivity::Four).count()
+    }
+
+    pub struct Day14;
+
+    impl Solution for Day14 {
+        type Input<'a> = &'a str;
+        type Config = ();
+        type Answer1 = usize;
+        type Answer2 = usize;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> usize {
+            part_1(input)
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> usize {
+            part_2(input)
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_14::*;
+    use day_10::knot;
+
+    fn raw_input() -> String {
+        common::input::from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
 
     #[test]
     fn part1_known() {
-        assert_eq!(part_1(&"flqrgnkx"), 8108);
-        assert_eq!(part_1_longhand(&"flqrgnkx"), 8108);
+        assert_eq!(part_1("flqrgnkx"), 8108);
+        assert_eq!(part_1_longhand("flqrgnkx"), 8108);
+    }
+
+    #[test]
+    fn test_knot_grid() {
+        let grid = KnotGrid::new("flqrgnkx", 8);
+        // The top-left corner of the example in the puzzle statement.
+        let corner: Vec<String> = grid
+            .to_string()
+            .lines()
+            .map(|line| line[..8].to_string())
+            .collect();
+        assert_eq!(
+            corner,
+            vec![
+                "##.#.#..", ".#.#.#.#", "....#.#.", "#.#.##.#", ".##.#...", "##..#..#", ".#...#..",
+                "##.#.##.",
+            ]
+        );
+        assert_eq!(grid.height(), 8);
+        assert!(grid.is_used(0, 0) && !grid.is_used(0, 2));
+        assert_eq!(grid.row_bytes(0)[0], 0b1101_0100);
+        assert_eq!(
+            grid.used().take(3).collect::<Vec<_>>(),
+            [(0, 0), (0, 1), (0, 3)]
+        );
+        assert_eq!(grid.used().count(), grid.count_used());
+        assert_eq!(grid.rows()[..], KnotGrid::new("flqrgnkx", 128).rows()[..8]);
+        for (row, &bits) in grid.rows().iter().enumerate() {
+            let hash = knot::hash(format!("flqrgnkx-{}", row).as_bytes());
+            assert_eq!(bits.to_be_bytes(), hash);
+        }
+
+        let grid = KnotGrid::from_rows(vec![1, 1 << 127]);
+        assert_eq!(grid.used().collect::<Vec<_>>(), [(0, 127), (1, 0)]);
+        assert!(grid
+            .to_string()
+            .starts_with(&format!("{}#\n#.", ".".repeat(127))));
+    }
+
+    #[test]
+    fn test_regions() {
+        // The top-left corner of the example, as in the puzzle statement:
+        // 11.2.3..
+        // .1.2.3.4
+        // ....5.6.
+        // 7.8.55.9
+        let grid = KnotGrid::new("flqrgnkx", 128);
+        let regions = grid.regions(Connectivity::Four);
+        let corner: Vec<Vec<Option<usize>>> = (0..4)
+            .map(|r| (0..8).map(|c| regions.label(r, c)).collect())
+            .collect();
+        let (a, b, c, d) = (corner[0][0], corner[0][3], corner[0][5], corner[1][7]);
+        let (e, f) = (corner[2][4], corner[2][6]);
+        let (g, h, i) = (corner[3][0], corner[3][2], corner[3][7]);
+        assert_eq!(
+            corner,
+            vec![
+                vec![a, a, None, b, None, c, None, None],
+                vec![None, a, None, b, None, c, None, d],
+                vec![None, None, None, None, e, None, f, None],
+                vec![g, None, h, None, e, e, None, i],
+            ]
+        );
+        let labels = [a, b, c, d, e, f, g, h, i];
+        for (n, label) in labels.iter().enumerate() {
+            assert!(labels[n + 1..].iter().all(|other| other != label));
+        }
+        assert_eq!(regions.region_at(0, 2), None);
+        assert_eq!(regions.count(), 1242);
+        assert_eq!(
+            regions.regions().iter().map(|r| r.size).sum::<usize>(),
+            grid.count_used()
+        );
+        assert_eq!(regions.labels().len(), 128 * 128);
+
+        let diagonal = KnotGrid::from_rows(vec![1 << 127, 1 << 126, 1 << 126]);
+        assert_eq!(diagonal.regions(Connectivity::Four).count(), 2);
+        let regions = diagonal.regions(Connectivity::Eight);
+        assert_eq!(regions.count(), 1);
+        assert_eq!(
+            regions.region_at(2, 1),
+            Some(&Region {
+                size: 3,
+                bounds: BoundingBox {
+                    top: 0,
+                    left: 0,
+                    bottom: 2,
+                    right: 1
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_large_region() {
+        // Far too deep for a recursive flood fill.
+        let grid = KnotGrid::from_rows(vec![u128::MAX; 4096]);
+        let regions = grid.regions(Connectivity::Four);
+        assert_eq!(regions.count(), 1);
+        assert_eq!(regions.regions()[0].size, 4096 * 128);
+        assert_eq!(regions.regions()[0].bounds.bottom, 4095);
     }
 
     #[test]
     fn part2_known() {
-        assert_eq!(part_2(&"flqrgnkx"), 1242);
+        assert_eq!(part_2("flqrgnkx"), 1242);
     }
 
     #[test]
     fn test_day_14() {
-        let input = input();
+        let input = raw_input();
+        let input = parse_input(&input).unwrap();
         assert_eq!(part_1(input), 8190);
         assert_eq!(part_1_longhand(input), 8190);
-        assert_eq!(part_2(&input), 1134);
+        assert_eq!(part_2(input), 1134);
     }
 }
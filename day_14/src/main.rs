@@ -1,7 +1,10 @@
+use common::input::InputError;
 use day_14::day_14;
 
-fn main() {
-    let input = day_14::input();
-    println!("part 1 => {}", day_14::part_1(&input));
-    println!("part 2 => {}", day_14::part_2(&input));
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let input = day_14::parse_input(&input)?;
+    println!("part 1 => {}", day_14::part_1(input));
+    println!("part 2 => {}", day_14::part_2(input));
+    Ok(())
 }
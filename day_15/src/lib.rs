@@ -1,17 +1,52 @@
 pub mod day_15 {
-    pub fn input() -> (u32, u32) {
-        let mut inputs = include_str!("../input.txt")
-            .lines()
-            .map(|l| l.trim().split_whitespace().last().unwrap().parse().unwrap());
-
-        let a = inputs.next().unwrap();
-        let b = inputs.next().unwrap();
-        match inputs.next() {
-            Some(i) => panic!("Expected no more lines, got {}", i),
-            None => (a, b),
+    use common::input::InputError;
+    use common::parse::{self, ParseError, Tokens};
+    use common::solution::Solution;
+    use std::io::Read;
+    use std::path::Path;
+    fn parse_line(l: &str) -> Result<u32, ParseError> {
+        let mut tokens = Tokens::new(l);
+        tokens.expect("Generator")?;
+        tokens.token("a generator name")?;
+        tokens.expect("starts")?;
+        tokens.expect("with")?;
+        let start = tokens.parse("a number")?;
+        tokens.end()?;
+        Ok(start)
+    }
+
+    pub fn parse_input(input: &str) -> Result<(u32, u32), ParseError> {
+        match parse::lines(input, parse_line)?[..] {
+            [a, b] => Ok((a, b)),
+            [_, _, _, ..] => {
+                let third = input.match_indices('\n').nth(1).unwrap().0 + 1;
+                Err(ParseError::at(
+                    input,
+                    third,
+                    input[third..].lines().next(),
+                    "end of input",
+                ))
+            }
+            _ => Err(ParseError::at_end(input, "two generators")),
         }
     }
 
+    pub fn from_reader<R>(reader: R) -> Result<(u32, u32), InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<(u32, u32), InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
+    }
+
     struct Generator {
         item: u32,
         modulus: u64,
@@ -57,12 +92,37 @@ pub mod day_15 {
             .filter(|(a, b)| a & 0xFFFF == b & 0xFFFF)
             .count()
     }
+
+    pub struct Day15;
+
+    impl Solution for Day15 {
+        type Input<'a> = (u32, u32);
+        type Config = ();
+        type Answer1 = usize;
+        type Answer2 = usize;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> usize {
+            part_1(input.0, input.1)
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> usize {
+            part_2(input.0, input.1)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_15::*;
 
+    fn input() -> (u32, u32) {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn part1_known() {
         assert_eq!(part_1(65, 8921), 588);
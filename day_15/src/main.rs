@@ -1,7 +1,10 @@
+use common::input::InputError;
 use day_15::day_15;
 
-fn main() {
-    let (a, b) = day_15::input();
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let (a, b) = day_15::parse_input(&input)?;
     println!("part 1 => {}", day_15::part_1(a, b));
     println!("part 2 => {}", day_15::part_2(a, b));
+    Ok(())
 }
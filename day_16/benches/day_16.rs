@@ -0,0 +1,40 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use cycle::cycle::{brent, floyd, hashing, nth_state, Cycle};
+use cycle::measure::{self, Counting};
+use day_16::day_16::{dance_once, from_path, part_2, Dancers};
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+type Detector = fn(&Vec<usize>, &dyn Fn(&Vec<usize>) -> Vec<usize>) -> Cycle;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let input = from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
+    let dancers = Dancers::letters(16);
+    let start: Vec<usize> = (0..16).collect();
+    let step = |line: &Vec<usize>| dance_once(&dancers, &input, line).unwrap();
+
+    let (_, peak) = measure::peak(|| part_2(&dancers, &input));
+    println!("day 16 part 2 algebra: peak memory {} bytes", peak);
+    c.bench_function("day 16 part 2 algebra", |b| {
+        b.iter(|| part_2(&dancers, &input).unwrap())
+    });
+
+    let detectors: [(&str, Detector); 3] = [
+        ("floyd", |s, f| floyd(s, f)),
+        ("brent", |s, f| brent(s, f)),
+        ("hashing", |s, f| hashing(s, f)),
+    ];
+    for &(name, detect) in detectors.iter() {
+        let run = || {
+            let cycle = detect(&start, &step);
+            nth_state(&start, step, &cycle, 1_000_000_000)
+        };
+        let (_, peak) = measure::peak(run);
+        println!("day 16 part 2 {}: peak memory {} bytes", name, peak);
+        c.bench_function(&format!("day 16 part 2 {}", name), |b| b.iter(run));
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,148 +1,642 @@
 pub mod day_16 {
+    use common::input::InputError;
+    use common::parse::ParseError;
+    use common::solution::{MaybeAnswer, Solution};
+    use cycle::cycle;
     use std::collections::HashMap;
+    use std::io::Read;
+    use std::path::Path;
 
+    #[derive(Debug, Clone, PartialEq, Eq)]
     pub enum Instruction {
-        Spin(u8),
-        Exchange(u8, u8),
-        Swap(char, char),
-    }
-
-    pub(crate) fn parse(s: &str) -> Instruction {
-        let mut chars = s.chars();
-        match chars.next().unwrap() {
-            's' => Instruction::Spin(chars.as_str().parse().unwrap()),
-            'p' => {
-                let one = chars.next().unwrap();
-                let slash = chars.next().unwrap();
-                if slash != '/' {
-                    panic!("Expected slash, got {}", slash);
+        Spin(usize),
+        Exchange(usize, usize),
+        Swap(String, String),
+    }
+
+    // The moves of a dance, each with where it starts in the text it was
+    // parsed from, so that `check` can point at one which can't be danced.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct Moves {
+        source: String,
+        moves: Vec<(Instruction, usize)>,
+    }
+
+    impl Moves {
+        pub fn len(&self) -> usize {
+            self.moves.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.moves.is_empty()
+        }
+
+        pub fn get(&self, i: usize) -> Option<&Instruction> {
+            self.moves.get(i).map(|(instr, _)| instr)
+        }
+
+        pub fn instructions(&self) -> impl Iterator<Item = &Instruction> {
+            self.moves.iter().map(|(instr, _)| instr)
+        }
+    }
+
+    fn parse_position(s: &str, start: usize, end: usize) -> Result<usize, ParseError> {
+        let text = &s[start..end];
+        text.parse()
+            .map_err(|_| ParseError::at(s, start, Some(text), "a position"))
+    }
+
+    fn parse_name(s: &str, start: usize, end: usize) -> Result<String, ParseError> {
+        if start == end {
+            Err(ParseError::at(s, start, None, "a program name"))
+        } else {
+            Ok(s[start..end].to_string())
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Result<Instruction, ParseError> {
+        match s.chars().next() {
+            Some('s') => Ok(Instruction::Spin(parse_position(s, 1, s.len())?)),
+            Some('p') => {
+                let slash = s.find('/').ok_or_else(|| ParseError::at_end(s, "'/'"))?;
+                let one = parse_name(s, 1, slash)?;
+                let two = parse_name(s, slash + 1, s.len())?;
+                Ok(Instruction::Swap(one, two))
+            }
+            Some('x') => {
+                let slash = s.find('/').ok_or_else(|| ParseError::at_end(s, "'/'"))?;
+                let one = parse_position(s, 1, slash)?;
+                let two = parse_position(s, slash + 1, s.len())?;
+                Ok(Instruction::Exchange(one, two))
+            }
+            Some(_) => Err(ParseError::at_char(s, 0, "'s', 'x' or 'p'")),
+            None => Err(ParseError::at_end(s, "a dance move")),
+        }
+    }
+
+    pub fn parse_input(input: &str) -> Result<Moves, ParseError> {
+        let mut offset = 0;
+        let moves = input
+            .split_terminator(',')
+            .map(|s| {
+                let start = offset + s.len() - s.trim_start().len();
+                offset += s.len() + 1;
+                let instr = parse(s.trim()).map_err(|e| e.shift(input, start))?;
+                Ok((instr, start))
+            })
+            .collect::<Result<_, ParseError>>()?;
+        Ok(Moves {
+            source: input.to_string(),
+            moves,
+        })
+    }
+
+    pub fn from_reader<R>(reader: R) -> Result<Moves, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<Moves, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
+    }
+
+    // The names of the dancers, in the order they start in.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Dancers {
+        names: Vec<String>,
+        indices: HashMap<String, usize>,
+    }
+
+    impl Dancers {
+        // Errors point into the names joined by commas, as the binary takes
+        // them.
+        pub fn new(names: Vec<String>) -> Result<Dancers, ParseError> {
+            let written = names.join(",");
+            let mut indices = HashMap::new();
+            let mut offset = 0;
+            for (i, name) in names.iter().enumerate() {
+                if name.is_empty() {
+                    return Err(ParseError::at(&written, offset, None, "a dancer's name"));
+                }
+                if indices.insert(name.clone(), i).is_some() {
+                    return Err(ParseError::at(
+                        &written,
+                        offset,
+                        Some(name),
+                        "a name not already taken",
+                    ));
                 }
-                let two = chars.next().unwrap();
-                Instruction::Swap(one, two)
+                offset += name.len() + 1;
             }
-            'x' => {
-                let mut components = chars.as_str().split('/');
-                let one = components.next().unwrap().parse().unwrap();
-                let two = components.next().unwrap().parse().unwrap();
-                match components.next() {
-                    Some(s) => panic!("Expected no more components, got {}", s),
-                    None => Instruction::Exchange(one, two),
+            Ok(Dancers { names, indices })
+        }
+
+        // `a` to `z`, then `aa`, `ab` and so on, as in spreadsheet columns.
+        pub fn letters(count: usize) -> Dancers {
+            let name = |mut i: usize| {
+                let mut name = vec![];
+                loop {
+                    name.push(b'a' + (i % 26) as u8);
+                    if i < 26 {
+                        break;
+                    }
+                    i = i / 26 - 1;
                 }
+                name.reverse();
+                String::from_utf8(name).unwrap()
+            };
+            Dancers::new((0..count).map(name).collect()).unwrap()
+        }
+
+        pub fn len(&self) -> usize {
+            self.names.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.names.is_empty()
+        }
+
+        pub fn index(&self, name: &str) -> Option<usize> {
+            self.indices.get(name).copied()
+        }
+
+        // Single-character names are run together, as in the puzzle; longer
+        // ones are separated by spaces.
+        pub fn render(&self, line: &[usize]) -> String {
+            let names: Vec<&str> = line.iter().map(|&i| self.names[i].as_str()).collect();
+            if self.names.iter().all(|n| n.chars().count() == 1) {
+                names.concat()
+            } else {
+                names.join(" ")
             }
-            s => panic!("Unexpected char: {}", s),
         }
     }
 
-    pub fn input() -> Vec<Instruction> {
-        let input = include_str!("../input.txt");
-        input
-            .split_terminator(',')
-            .map(|s| parse(s.trim()))
-            .collect::<Vec<_>>()
+    // Makes sure every move can be danced by these dancers.
+    pub fn check(dancers: &Dancers, moves: &Moves) -> Result<(), ParseError> {
+        let source = moves.source.as_str();
+        for (instr, start) in moves.moves.iter() {
+            let text = &source[*start..];
+            let text = text[..text.find(',').unwrap_or(text.len())].trim_end();
+            // Both operands of an exchange or a partner swap, with where they
+            // start in the source.
+            let operands = || {
+                let slash = text.find('/').unwrap();
+                [
+                    (start + 1, &text[1..slash]),
+                    (start + slash + 1, &text[slash + 1..]),
+                ]
+            };
+            match instr {
+                Instruction::Spin(_) => {
+                    if dancers.is_empty() {
+                        return Err(ParseError::at(
+                            source,
+                            *start,
+                            Some(text),
+                            "a move with some dancers to spin",
+                        ));
+                    }
+                }
+                Instruction::Exchange(i, j) => {
+                    for (&position, &(offset, token)) in [*i, *j].iter().zip(operands().iter()) {
+                        if position >= dancers.len() {
+                            return Err(ParseError::at(
+                                source,
+                                offset,
+                                Some(token),
+                                format!("a position below {}", dancers.len()),
+                            ));
+                        }
+                    }
+                }
+                Instruction::Swap(_, _) => {
+                    for &(offset, name) in operands().iter() {
+                        if dancers.index(name).is_none() {
+                            return Err(ParseError::at(
+                                source,
+                                offset,
+                                Some(name),
+                                "one of the dancers",
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
     }
 
-    const ASCII_A: u8 = 97;
+    // A line has no text of its own, so errors point into it written out
+    // with commas.
+    fn check_line(dancers: &Dancers, line: &[usize]) -> Result<(), ParseError> {
+        let written = line
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut seen = vec![false; dancers.len()];
+        let mut offset = 0;
+        for &dancer in line.iter().take(dancers.len()) {
+            if dancer >= dancers.len() || seen[dancer] {
+                return Err(ParseError::at(
+                    &written,
+                    offset,
+                    Some(&dancer.to_string()),
+                    format!("a dancer below {} not already in the line", dancers.len()),
+                ));
+            }
+            seen[dancer] = true;
+            offset += dancer.to_string().len() + 1;
+        }
+        match line.get(dancers.len()) {
+            None if line.len() < dancers.len() => Err(ParseError::at_end(
+                &written,
+                format!("{} dancers in the line", dancers.len()),
+            )),
+            None => Ok(()),
+            Some(extra) => Err(ParseError::at(
+                &written,
+                offset,
+                Some(&extra.to_string()),
+                format!("only {} dancers in the line", dancers.len()),
+            )),
+        }
+    }
 
+    // The moves must already have passed `check`.
     fn part_1_step(
-        program: &mut Vec<u8>,
-        character_to_index: &mut Vec<usize>,
-        instructions: &[Instruction],
+        dancers: &Dancers,
+        program: &mut [usize],
+        character_to_index: &mut [usize],
+        moves: &Moves,
     ) {
         let size = program.len();
-        for instr in instructions {
+        for instr in moves.instructions() {
             match instr {
                 Instruction::Spin(offset) => {
                     // Everything in location x must now go to x-i
                     for (i, loc) in character_to_index.iter_mut().enumerate() {
-                        let new_index = (*loc + (*offset as usize)) % size;
+                        let new_index = (*loc + offset) % size;
                         *loc = new_index;
-                        program[new_index] = i as u8 + ASCII_A;
+                        program[new_index] = i;
                     }
                 }
                 Instruction::Swap(i, j) => {
-                    let index_i = (*i as u8 - ASCII_A) as usize;
-                    let index_j = (*j as u8 - ASCII_A) as usize;
+                    let index_i = dancers.index(i).unwrap();
+                    let index_j = dancers.index(j).unwrap();
 
                     character_to_index.swap(index_i, index_j);
 
-                    program[character_to_index[index_i]] = *i as u8;
-                    program[character_to_index[index_j]] = *j as u8;
+                    program[character_to_index[index_i]] = index_i;
+                    program[character_to_index[index_j]] = index_j;
                 }
                 Instruction::Exchange(i, j) => {
-                    let char_i = program[*i as usize];
-                    let char_j = program[*j as usize];
-                    program[*i as usize] = char_j;
-                    program[*j as usize] = char_i;
+                    let char_i = program[*i];
+                    let char_j = program[*j];
+                    program[*i] = char_j;
+                    program[*j] = char_i;
 
-                    character_to_index[(char_i as u8 - ASCII_A) as usize] = *j as usize;
-                    character_to_index[(char_j as u8 - ASCII_A) as usize] = *i as usize;
+                    character_to_index[char_i] = *j;
+                    character_to_index[char_j] = *i;
                 }
             }
         }
     }
 
-    pub fn part_1(size: usize, instructions: &[Instruction]) -> String {
-        let mut program = vec![0u8; size];
-        let mut character_to_index = vec![0; size];
-        for (i, loc) in program.iter_mut().enumerate() {
-            character_to_index[i] = i;
-            *loc = i as u8 + ASCII_A;
-        }
+    pub fn part_1(dancers: &Dancers, moves: &Moves) -> Result<String, ParseError> {
+        check(dancers, moves)?;
+        let mut program: Vec<usize> = (0..dancers.len()).collect();
+        let mut character_to_index = program.clone();
+        part_1_step(dancers, &mut program, &mut character_to_index, moves);
+        Ok(dancers.render(&program))
+    }
 
-        part_1_step(&mut program, &mut character_to_index, instructions);
-        String::from_utf8(program).unwrap()
+    // A dance acts on positions (spins and exchanges) and, separately, on
+    // labels (partners). The two kinds of move commute, so any sequence of
+    // moves is a permutation of each.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Dance {
+        // The dancer at position `i` came from position `positions[i]`.
+        positions: Vec<usize>,
+        // The dancer labelled `x` is relabelled `labels[x]`.
+        labels: Vec<usize>,
     }
 
-    pub fn part_2(size: usize, instructions: &[Instruction]) -> String {
-        let mut program = vec![0u8; size];
-        let mut character_to_index = vec![0; size];
-        for (i, loc) in program.iter_mut().enumerate() {
-            character_to_index[i] = i;
-            *loc = i as u8 + ASCII_A;
+    impl Dance {
+        pub fn identity(size: usize) -> Dance {
+            Dance {
+                positions: (0..size).collect(),
+                labels: (0..size).collect(),
+            }
+        }
+
+        pub fn new(dancers: &Dancers, moves: &Moves) -> Result<Dance, ParseError> {
+            check(dancers, moves)?;
+            let size = dancers.len();
+            let mut dance = Dance::identity(size);
+            // Which label is currently called `x`.
+            let mut called: Vec<usize> = (0..size).collect();
+            for instr in moves.instructions() {
+                match instr {
+                    Instruction::Spin(offset) => {
+                        dance.positions.rotate_right(offset % size);
+                    }
+                    Instruction::Exchange(i, j) => {
+                        dance.positions.swap(*i, *j);
+                    }
+                    Instruction::Swap(i, j) => {
+                        let i = dancers.index(i).unwrap();
+                        let j = dancers.index(j).unwrap();
+                        called.swap(i, j);
+                        dance.labels[called[i]] = i;
+                        dance.labels[called[j]] = j;
+                    }
+                }
+            }
+            Ok(dance)
         }
-        const MAX: usize = 1000000000;
 
-        let mut seen: HashMap<String, usize> = HashMap::new();
-        seen.insert(String::from_utf8(program.clone()).unwrap(), 0);
+        pub fn size(&self) -> usize {
+            self.positions.len()
+        }
 
-        let period = (1..=MAX)
-            .filter_map(|i| {
-                part_1_step(&mut program, &mut character_to_index, instructions);
-                let cloned = String::from_utf8(program.clone()).unwrap();
-                seen.insert(cloned, i).map(|old| (i - old, i))
-            })
-            .next();
-
-        match period {
-            None => String::from_utf8(program).unwrap(),
-            Some((period, start)) => {
-                let iterations = (MAX - start) % period;
-                for _ in 0..iterations {
-                    part_1_step(&mut program, &mut character_to_index, instructions);
+        // This dance followed by `other`.
+        pub fn then(&self, other: &Dance) -> Dance {
+            Dance {
+                positions: other.positions.iter().map(|&p| self.positions[p]).collect(),
+                labels: self.labels.iter().map(|&l| other.labels[l]).collect(),
+            }
+        }
+
+        // This dance done `n` times, by repeated squaring.
+        pub fn pow(&self, mut n: u64) -> Dance {
+            let mut result = Dance::identity(self.size());
+            let mut square = self.clone();
+            while n > 0 {
+                if n % 2 == 1 {
+                    result = result.then(&square);
                 }
-                String::from_utf8(program).unwrap()
+                square = square.then(&square);
+                n /= 2;
+            }
+            result
+        }
+
+        // The line of labels after dancing, given the line beforehand.
+        pub fn apply(&self, line: &[usize]) -> Vec<usize> {
+            self.positions
+                .iter()
+                .map(|&p| self.labels[line[p]])
+                .collect()
+        }
+    }
+
+    // The line after `n` dances.
+    pub fn after(dancers: &Dancers, moves: &Moves, n: u64) -> Result<String, ParseError> {
+        let start: Vec<usize> = (0..dancers.len()).collect();
+        let dance = Dance::new(dancers, moves)?;
+        Ok(dancers.render(&dance.pow(n).apply(&start)))
+    }
+
+    pub fn part_2(dancers: &Dancers, moves: &Moves) -> Result<String, ParseError> {
+        after(dancers, moves, 1_000_000_000)
+    }
+
+    // Dances once, one move at a time, from `line`: every dancer's index, in
+    // the order they're standing.
+    pub fn dance_once(
+        dancers: &Dancers,
+        moves: &Moves,
+        line: &[usize],
+    ) -> Result<Vec<usize>, ParseError> {
+        check(dancers, moves)?;
+        check_line(dancers, line)?;
+        Ok(dance_checked(dancers, moves, line))
+    }
+
+    fn dance_checked(dancers: &Dancers, moves: &Moves, line: &[usize]) -> Vec<usize> {
+        let mut program = line.to_vec();
+        let mut character_to_index = vec![0; line.len()];
+        for (i, &dancer) in line.iter().enumerate() {
+            character_to_index[dancer] = i;
+        }
+        part_1_step(dancers, &mut program, &mut character_to_index, moves);
+        program
+    }
+
+    // Dances until the line repeats, and skips the remaining whole cycles.
+    pub fn part_2_simulate(dancers: &Dancers, moves: &Moves) -> Result<String, ParseError> {
+        check(dancers, moves)?;
+        let start: Vec<usize> = (0..dancers.len()).collect();
+        let step = |line: &Vec<usize>| dance_checked(dancers, moves, line);
+        let cycle = cycle::brent(&start, step);
+        Ok(dancers.render(&cycle::nth_state(&start, step, &cycle, 1_000_000_000)))
+    }
+
+    pub struct Config {
+        pub dancers: Dancers,
+    }
+
+    impl Default for Config {
+        fn default() -> Config {
+            Config {
+                dancers: Dancers::letters(16),
             }
         }
     }
+
+    pub struct Day16;
+
+    impl Solution for Day16 {
+        type Input<'a> = Moves;
+        type Config = Config;
+        // The default sixteen dancers can't dance every input.
+        type Answer1 = MaybeAnswer<String>;
+        type Answer2 = MaybeAnswer<String>;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, config: &Config) -> MaybeAnswer<String> {
+            MaybeAnswer(part_1(&config.dancers, input).ok())
+        }
+
+        fn part_2(input: &Self::Input<'_>, config: &Config) -> MaybeAnswer<String> {
+            MaybeAnswer(part_2(&config.dancers, input).ok())
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_16::*;
+    use common::solution::{MaybeAnswer, Solution};
+
+    fn input() -> Moves {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
 
     #[test]
     fn part1_known() {
-        let instructions: Vec<Instruction> = vec!["s1", "x3/4", "pe/b"]
-            .iter()
-            .map(|i| parse(i))
-            .collect();
-        assert_eq!(part_1(5, &instructions), "baedc");
+        let instructions = parse_input("s1,x3/4,pe/b").unwrap();
+        assert_eq!(
+            part_1(&Dancers::letters(5), &instructions).unwrap(),
+            "baedc"
+        );
+    }
+
+    #[test]
+    fn part1_known_solution() {
+        let input = Day16::parse("s1,x3/4,pe/b").unwrap();
+        assert_eq!(
+            Day16::part_1(
+                &input,
+                &Config {
+                    dancers: Dancers::letters(5)
+                }
+            ),
+            MaybeAnswer(Some("baedc".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse_input("s1,x3/z,pe/b").unwrap_err();
+        assert_eq!(error.column, 7);
+        assert_eq!(error.expected, "a position");
+        assert_eq!(parse_input("s1,pe-b").unwrap_err().column, 8);
+        assert_eq!(
+            parse_input("s1,p/b").unwrap_err().expected,
+            "a program name"
+        );
     }
 
     #[test]
     fn test_day_16() {
         let input = input();
-        assert_eq!(part_1(16, &input), "ebjpfdgmihonackl");
-        assert_eq!(part_2(16, &input), "abocefghijklmndp");
+        let dancers = Dancers::letters(16);
+        assert_eq!(part_1(&dancers, &input).unwrap(), "ebjpfdgmihonackl");
+        assert_eq!(part_2(&dancers, &input).unwrap(), "abocefghijklmndp");
+        assert_eq!(
+            part_2_simulate(&dancers, &input).unwrap(),
+            "abocefghijklmndp"
+        );
+    }
+
+    #[test]
+    fn test_dance() {
+        let instructions = parse_input("s1,x3/4,pe/b").unwrap();
+        let five = Dancers::letters(5);
+        assert_eq!(after(&five, &instructions, 1).unwrap(), "baedc");
+        assert_eq!(after(&five, &instructions, 2).unwrap(), "ceadb");
+        assert_eq!(after(&five, &instructions, 0).unwrap(), "abcde");
+
+        let input = input();
+        let dancers = Dancers::letters(16);
+        let dance = Dance::new(&dancers, &input).unwrap();
+        assert_eq!(dance.pow(3), dance.then(&dance).then(&dance));
+        assert_eq!(dance.pow(7).then(&dance.pow(5)), dance.pow(12));
+        for &n in &[1, 2, 5, 63, 64, 65] {
+            assert_eq!(
+                after(&dancers, &input, n).unwrap(),
+                after(&dancers, &input, n + 60).unwrap()
+            );
+        }
+        assert_eq!(
+            after(&dancers, &input, u64::MAX).unwrap(),
+            after(&dancers, &input, u64::MAX % 60).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_dancers() {
+        let dancers = Dancers::letters(705);
+        assert_eq!(dancers.index("z"), Some(25));
+        assert_eq!(dancers.index("aa"), Some(26));
+        assert_eq!(dancers.index("zz"), Some(701));
+        assert_eq!(dancers.index("aaa"), Some(702));
+
+        let names = ["alice", "bob", "carol", "dave"];
+        let dancers = Dancers::new(names.iter().map(|n| n.to_string()).collect()).unwrap();
+        let instructions = parse_input("s1,x3/2,palice/dave").unwrap();
+        assert_eq!(
+            instructions.get(2),
+            Some(&Instruction::Swap("alice".into(), "dave".into()))
+        );
+        assert_eq!(
+            part_1(&dancers, &instructions).unwrap(),
+            "alice dave carol bob"
+        );
+        assert_eq!(
+            after(&dancers, &instructions, 1).unwrap(),
+            "alice dave carol bob"
+        );
+
+        // Thousands of dancers, in a different starting order.
+        let big = Dancers::new((0..3000).rev().map(|i| format!("d{}", i)).collect()).unwrap();
+        let moves = "s1234,x0/2999,pd5/d2999,x17/5,s7";
+        let instructions = parse_input(moves).unwrap();
+        let three = parse_input(&[moves; 3].join(",")).unwrap();
+        assert_eq!(
+            after(&big, &instructions, 3).unwrap(),
+            part_1(&big, &three).unwrap()
+        );
+        assert!(part_1(&big, &Moves::default())
+            .unwrap()
+            .starts_with("d2999 d2998 "));
+    }
+
+    #[test]
+    fn test_check() {
+        let sixteen = Dancers::letters(16);
+        let error = part_1(&sixteen, &parse_input("s1,pa/zz").unwrap()).unwrap_err();
+        assert_eq!((error.column, error.token.as_deref()), (7, Some("zz")));
+        let error = part_2(&sixteen, &parse_input("x0/20").unwrap()).unwrap_err();
+        assert_eq!(
+            (error.column, error.expected.as_str()),
+            (4, "a position below 16")
+        );
+        let moves = parse_input("x16/1").unwrap();
+        let line: Vec<usize> = (0..16).rev().collect();
+        assert_eq!(dance_once(&sixteen, &moves, &line).unwrap_err().column, 2);
+        assert!(part_2_simulate(&sixteen, &moves).is_err());
+
+        // Errors point into the input as it was written.
+        let error = part_1(&sixteen, &parse_input("s01, x03/4,\n pa/ zz").unwrap()).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 5));
+        assert_eq!(error.token.as_deref(), Some(" zz"));
+        let error = part_1(&sixteen, &parse_input("s1, x016/4").unwrap()).unwrap_err();
+        assert_eq!((error.column, error.token.as_deref()), (6, Some("016")));
+
+        // The line must be every dancer exactly once.
+        let moves = parse_input("pa/p").unwrap();
+        let error = dance_once(&sixteen, &moves, &[0, 1]).unwrap_err();
+        assert_eq!(error.expected, "16 dancers in the line");
+        let mut line: Vec<usize> = (0..17).collect();
+        let error = dance_once(&sixteen, &moves, &line).unwrap_err();
+        assert_eq!(error.token.as_deref(), Some("16"));
+        line[15] = 3;
+        line.pop();
+        let error = dance_once(&sixteen, &moves, &line).unwrap_err();
+        assert_eq!((error.column, error.token.as_deref()), (36, Some("3")));
+        line[15] = 15;
+        assert_eq!(dance_once(&sixteen, &moves, &line).unwrap()[0], 15);
+
+        let nobody = Dancers::letters(0);
+        assert!(after(&nobody, &parse_input("s1").unwrap(), 5).is_err());
+        assert_eq!(after(&nobody, &Moves::default(), 5).unwrap(), "");
+
+        let error = Dancers::new(vec!["a".into(), "b".into(), "a".into()]).unwrap_err();
+        assert_eq!((error.column, error.token.as_deref()), (5, Some("a")));
+        assert!(Dancers::new(vec!["a".into(), "".into()]).is_err());
     }
 }
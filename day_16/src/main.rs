@@ -1,7 +1,16 @@
+use common::input::InputError;
 use day_16::day_16;
 
-fn main() {
-    let input = day_16::input();
-    println!("part 1 => {}", day_16::part_1(16, &input));
-    println!("part 2 => {}", day_16::part_2(16, &input));
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let input = day_16::parse_input(&input)?;
+    // Optionally the dancers' names in their starting order, such as
+    // `alice,bob,carol`, after the input file.
+    let dancers = match std::env::args().nth(2) {
+        Some(names) => day_16::Dancers::new(names.split(',').map(String::from).collect())?,
+        None => day_16::Dancers::letters(16),
+    };
+    println!("part 1 => {}", day_16::part_1(&dancers, &input)?);
+    println!("part 2 => {}", day_16::part_2(&dancers, &input)?);
+    Ok(())
 }
@@ -1,8 +1,31 @@
 pub mod day_17 {
+    use common::input::InputError;
+    use common::parse::{ParseError, Tokens};
+    use common::solution::Solution;
+    use std::io::Read;
+    use std::path::Path;
 
-    pub fn input() -> usize {
-        let input = include_str!("../input.txt");
-        input.trim().parse().unwrap()
+    pub fn parse_input(input: &str) -> Result<usize, ParseError> {
+        let mut tokens = Tokens::new(input);
+        let n = tokens.parse("a number")?;
+        tokens.end()?;
+        Ok(n)
+    }
+
+    pub fn from_reader<R>(reader: R) -> Result<usize, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<usize, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
     }
 
     struct CircularBuffer<T> {
@@ -70,12 +93,49 @@ pub mod day_17 {
 
         after_zero
     }
+
+    pub struct Config {
+        pub part_2_insertions: usize,
+    }
+
+    impl Default for Config {
+        fn default() -> Config {
+            Config {
+                part_2_insertions: 50000000,
+            }
+        }
+    }
+
+    pub struct Day17;
+
+    impl Solution for Day17 {
+        type Input<'a> = usize;
+        type Config = Config;
+        type Answer1 = u32;
+        type Answer2 = u32;
+
+        fn parse(input: &str) -> Result<usize, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &usize, _: &Config) -> u32 {
+            part_1(*input)
+        }
+
+        fn part_2(input: &usize, config: &Config) -> u32 {
+            part_2(*input, config.part_2_insertions)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_17::*;
 
+    fn input() -> usize {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn part1_known() {
         assert_eq!(part_1(3), 638);
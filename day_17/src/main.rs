@@ -1,7 +1,10 @@
+use common::input::InputError;
 use day_17::day_17;
 
-fn main() {
-    let input = day_17::input();
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let input = day_17::parse_input(&input)?;
     println!("part 1 => {}", day_17::part_1(input));
     println!("part 2 => {}", day_17::part_2(input, 50000000));
+    Ok(())
 }
@@ -1,7 +1,23 @@
+use common::input::InputError;
+use common::solution::MaybeAnswer;
 use day_18::day_18;
+use std::process;
+use vm::compiled::Backend;
 
-fn main() {
-    let input = day_18::input();
-    println!("part 1 => {}", day_18::part_1(&input));
-    println!("part 2 => {}", day_18::part_2(&input));
+const USAGE: &str = "Usage: day_18 [FILE] [interpreter|compiled]";
+
+fn main() -> Result<(), InputError> {
+    // Optionally `interpreter` or `compiled`, after the input file.
+    let backend = match std::env::args().nth(2) {
+        Some(backend) => backend.parse().unwrap_or_else(|e| {
+            eprintln!("day_18: {}\n{}", e, USAGE);
+            process::exit(2);
+        }),
+        None => Backend::default(),
+    };
+    let input = common::input::from_args()?;
+    let input = day_18::parse_input(&input)?;
+    println!("part 1 => {}", MaybeAnswer(day_18::part_1(backend, &input)));
+    println!("part 2 => {}", day_18::part_2(backend, &input));
+    Ok(())
 }
@@ -1,4 +1,9 @@
 pub mod day_19 {
+    use common::input::InputError;
+    use common::parse::{self, ParseError};
+    use common::solution::Solution;
+    use std::io::Read;
+    use std::path::Path;
 
     #[derive(Clone, Copy, PartialEq, Eq)]
     pub enum Square {
@@ -9,29 +14,59 @@ pub mod day_19 {
         Char(char),
     }
 
-    fn parse(c: char) -> Square {
+    fn parse(c: char) -> Option<Square> {
         match c {
-            ' ' => Square::Empty,
-            '|' => Square::Vertical,
-            '-' => Square::Horizontal,
-            '+' => Square::Cross,
-            c => Square::Char(c),
+            ' ' => Some(Square::Empty),
+            '|' => Some(Square::Vertical),
+            '-' => Some(Square::Horizontal),
+            '+' => Some(Square::Cross),
+            c if c.is_alphabetic() => Some(Square::Char(c)),
+            _ => None,
         }
     }
 
-    pub fn input() -> Vec<Vec<Square>> {
-        let input = include_str!("../input.txt");
-        let mut output = input
-            .lines()
-            .map(|l| l.chars().map(parse).collect())
-            .collect::<Vec<Vec<_>>>();
+    pub fn parse_input(input: &str) -> Result<Vec<Vec<Square>>, ParseError> {
+        let mut output: Vec<Vec<Square>> = parse::lines(input, |l| {
+            l.char_indices()
+                .map(|(i, c)| {
+                    parse(c).ok_or_else(|| ParseError::at_char(l, i, "a path or a letter"))
+                })
+                .collect()
+        })?;
+        if !output
+            .first()
+            .is_some_and(|row| row.contains(&Square::Vertical))
+        {
+            return Err(ParseError::at(
+                input,
+                0,
+                input.lines().next(),
+                "a '|' on the first line",
+            ));
+        }
         let max_len = output.iter().map(|l| l.len()).max().unwrap();
         for row in output.iter_mut() {
             if row.len() < max_len {
-                row.extend(std::iter::repeat(Square::Empty).take(max_len - row.len()))
+                row.extend(std::iter::repeat_n(Square::Empty, max_len - row.len()))
             }
         }
-        output
+        Ok(output)
+    }
+
+    pub fn from_reader<R>(reader: R) -> Result<Vec<Vec<Square>>, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<Vec<Vec<Square>>, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
     }
 
     pub fn execute(square: &[Vec<Square>]) -> (String, u32) {
@@ -119,12 +154,37 @@ pub mod day_19 {
             };
         }
     }
+
+    pub struct Day19;
+
+    impl Solution for Day19 {
+        type Input<'a> = Vec<Vec<Square>>;
+        type Config = ();
+        type Answer1 = String;
+        type Answer2 = u32;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> String {
+            execute(input).0
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> u32 {
+            execute(input).1
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_19::*;
 
+    fn input() -> Vec<Vec<Square>> {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn test_day_1() {
         let (part_1, part_2) = execute(&input());
@@ -1,8 +1,11 @@
+use common::input::InputError;
 use day_19::day_19;
 
-fn main() {
-    let input = day_19::input();
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let input = day_19::parse_input(&input)?;
     let (part_1, part_2) = day_19::execute(&input);
     println!("part 1 => {}", part_1);
     println!("part 2 => {}", part_2);
+    Ok(())
 }
@@ -1,22 +1,31 @@
 pub mod day_2 {
+    use common::input::InputError;
+    use common::parse::{self, ParseError, Tokens};
+    use common::solution::{MaybeAnswer, Solution};
+    use std::io::Read;
+    use std::path::Path;
 
     use std::cmp::Ordering;
     use std::collections::HashSet;
 
-    pub fn input() -> Vec<Vec<u32>> {
-        let input = include_str!("../input.txt");
-        input
-            .lines()
-            .map(|l| {
-                l.trim()
-                    .split_whitespace()
-                    .map(|i| {
-                        i.parse()
-                            .unwrap_or_else(|_| panic!("{} wasn't a valid u32", i))
-                    })
-                    .collect::<Vec<u32>>()
-            })
-            .collect::<Vec<Vec<u32>>>()
+    pub fn parse_input(input: &str) -> Result<Vec<Vec<u32>>, ParseError> {
+        parse::lines(input, |l| Tokens::new(l).parse_rest("a number"))
+    }
+
+    pub fn from_reader<R>(reader: R) -> Result<Vec<Vec<u32>>, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<Vec<Vec<u32>>, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
     }
 
     fn min_max<T, I>(i: &mut I) -> Option<(T, T)>
@@ -49,19 +58,22 @@ pub mod day_2 {
         for i in iter {
             for s in &seen {
                 let s = *s;
+                // Nothing divides by zero.
                 match u32::cmp(&s, &i) {
                     Ordering::Less => {
-                        if i % s == 0 {
+                        if s != 0 && i % s == 0 {
                             return Some((i, s));
                         };
                     }
                     Ordering::Greater => {
-                        if s % i == 0 {
+                        if i != 0 && s % i == 0 {
                             return Some((s, i));
                         };
                     }
                     Ordering::Equal => {
-                        return Some((i, s));
+                        if i != 0 {
+                            return Some((i, s));
+                        }
                     }
                 }
             }
@@ -86,29 +98,55 @@ pub mod day_2 {
             .sum()
     }
 
-    pub fn part_2<I, J>(numbers: &mut I) -> u32
+    // `None` if some row has no pair of numbers where one divides the other.
+    pub fn part_2<I, J>(numbers: &mut I) -> Option<u32>
     where
         I: Iterator<Item = J>,
         J: Iterator<Item = u32>,
     {
         numbers
             .map(|mut row| {
-                let (bigger, smaller) = even_divisor(&mut row).unwrap();
-                bigger / smaller
+                let (bigger, smaller) = even_divisor(&mut row)?;
+                Some(bigger / smaller)
             })
             .sum()
     }
+
+    pub struct Day2;
+
+    impl Solution for Day2 {
+        type Input<'a> = Vec<Vec<u32>>;
+        type Config = ();
+        type Answer1 = u32;
+        type Answer2 = MaybeAnswer<u32>;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_1(&mut input.iter().map(|r| r.iter().cloned()))
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> MaybeAnswer<u32> {
+            MaybeAnswer(part_2(&mut input.iter().map(|r| r.iter().cloned())))
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_2::*;
 
+    fn input() -> Vec<Vec<u32>> {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn part1_known() {
         assert_eq!(
             part_1(
-                &mut vec![vec![5, 1, 9, 5], vec![7, 5, 3], vec![2, 4, 6, 8]]
+                &mut [vec![5, 1, 9, 5], vec![7, 5, 3], vec![2, 4, 6, 8]]
                     .iter()
                     .map(|r| r.iter().cloned())
             ),
@@ -120,11 +158,18 @@ mod tests {
     fn part2_known() {
         assert_eq!(
             part_2(
-                &mut vec![vec![5, 9, 2, 8], vec![9, 4, 7, 3], vec![3, 8, 6, 5]]
+                &mut [vec![5, 9, 2, 8], vec![9, 4, 7, 3], vec![3, 8, 6, 5]]
                     .iter()
                     .map(|r| r.iter().cloned())
             ),
-            9
+            Some(9)
+        );
+        let input = parse_input("1 2\n3").unwrap();
+        assert_eq!(part_2(&mut input.iter().map(|r| r.iter().cloned())), None);
+        let input = parse_input("0 3 0 5 10").unwrap();
+        assert_eq!(
+            part_2(&mut input.iter().map(|r| r.iter().cloned())),
+            Some(2)
         );
     }
 
@@ -134,6 +179,6 @@ mod tests {
         let answer = part_1(&mut input.iter().map(|r| r.iter().cloned()));
         assert_eq!(answer, 44887);
         let answer = part_2(&mut input.iter().map(|r| r.iter().cloned()));
-        assert_eq!(answer, 242);
+        assert_eq!(answer, Some(242));
     }
 }
@@ -1,13 +1,17 @@
+use common::input::InputError;
+use common::solution::MaybeAnswer;
 use day_2::day_2;
 
-fn main() {
-    let input = day_2::input();
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let input = day_2::parse_input(&input)?;
     println!(
         "part 1 => {}",
         day_2::part_1(&mut input.iter().map(|r| r.iter().cloned()))
     );
     println!(
         "part 2 => {}",
-        day_2::part_2(&mut input.iter().map(|r| r.iter().cloned()))
+        MaybeAnswer(day_2::part_2(&mut input.iter().map(|r| r.iter().cloned())))
     );
+    Ok(())
 }
@@ -1,6 +1,12 @@
 pub mod day_22 {
+    use common::input::InputError;
+    use common::parse::{self, ParseError};
+    use common::solution::Solution;
     use std::collections::HashMap;
+    use std::io::Read;
+    use std::path::Path;
 
+    #[derive(Clone)]
     enum State {
         Clean,
         Weakened,
@@ -8,6 +14,7 @@ pub mod day_22 {
         Infected,
     }
 
+    #[derive(Clone)]
     enum Direction {
         Left,
         Right,
@@ -44,6 +51,7 @@ pub mod day_22 {
         }
     }
 
+    #[derive(Clone)]
     pub struct Board {
         squares: HashMap<(i32, i32), State>,
         position: (i32, i32),
@@ -61,42 +69,54 @@ pub mod day_22 {
     }
 
     impl Board {
-        pub(crate) fn parse(s: &str) -> Board {
+        pub(crate) fn parse(s: &str) -> Result<Board, ParseError> {
+            let rows = parse::lines(s, |l| {
+                let start = l.len() - l.trim_start().len();
+                l.trim()
+                    .char_indices()
+                    .map(|(i, ch)| match ch {
+                        '#' => Ok(State::Infected),
+                        '.' => Ok(State::Clean),
+                        _ => Err(ParseError::at_char(l, start + i, "'#' or '.'")),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })?;
+
+            let num_rows = rows.len() as i32;
+            let num_cols = rows.first().map_or(0, |r| r.len() as i32);
             let mut output = HashMap::new();
-            let mut num_cols = 0;
-            let mut num_rows = 0;
-            for l in s.lines() {
-                let mut col = 0;
-                for ch in l.trim().chars() {
-                    output.insert(
-                        (num_rows, col),
-                        match ch {
-                            '#' => State::Infected,
-                            '.' => State::Clean,
-                            c => {
-                                panic!("Unexpected char: {}", c);
-                            }
-                        },
-                    );
-                    col += 1;
-                }
-                if num_rows == 0 {
-                    num_cols = col;
+            for (row, states) in rows.into_iter().enumerate() {
+                for (col, state) in states.into_iter().enumerate() {
+                    output.insert((row as i32, col as i32), state);
                 }
-                num_rows += 1;
             }
 
-            Board {
+            Ok(Board {
                 squares: output,
                 position: (num_rows / 2, num_cols / 2),
                 direction: Direction::Up,
-            }
+            })
         }
     }
 
-    pub fn input() -> Board {
-        let input = include_str!("../input.txt");
-        Board::parse(&input)
+    pub fn parse_input(input: &str) -> Result<Board, ParseError> {
+        Board::parse(input)
+    }
+
+    pub fn from_reader<R>(reader: R) -> Result<Board, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<Board, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
     }
 
     // Returns whether we caused an infection.
@@ -166,25 +186,75 @@ pub mod day_22 {
         }
         count
     }
+
+    pub struct Config {
+        pub part_1_bursts: u32,
+        pub part_2_bursts: u32,
+    }
+
+    impl Default for Config {
+        fn default() -> Config {
+            Config {
+                part_1_bursts: 10000,
+                part_2_bursts: 10000000,
+            }
+        }
+    }
+
+    pub struct Day22;
+
+    impl Solution for Day22 {
+        type Input<'a> = Board;
+        type Config = Config;
+        type Answer1 = usize;
+        type Answer2 = usize;
+
+        fn parse(input: &str) -> Result<Board, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Board, config: &Config) -> usize {
+            part_1(input.clone(), config.part_1_bursts)
+        }
+
+        fn part_2(input: &Board, config: &Config) -> usize {
+            part_2(input.clone(), config.part_2_bursts)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_22::*;
+    use common::solution::Solution;
+
+    fn input() -> Board {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
 
     #[test]
     fn part1_known() {
-        let input = Board::parse("..#\n#..\n...");
+        let input = Board::parse("..#\n#..\n...").unwrap();
         assert_eq!(part_1(input, 70), 41);
-        let input = Board::parse("..#\n#..\n...");
+        let input = Board::parse("..#\n#..\n...").unwrap();
         assert_eq!(part_1(input, 10000), 5587);
     }
 
+    #[test]
+    fn part1_known_solution() {
+        let input = Day22::parse("..#\n#..\n...").unwrap();
+        let config = Config {
+            part_1_bursts: 70,
+            ..Config::default()
+        };
+        assert_eq!(Day22::part_1(&input, &config), 41);
+    }
+
     #[test]
     fn part2_known() {
-        let input = Board::parse("..#\n#..\n...");
+        let input = Board::parse("..#\n#..\n...").unwrap();
         assert_eq!(part_2(input, 100), 26);
-        let input = Board::parse("..#\n#..\n...");
+        let input = Board::parse("..#\n#..\n...").unwrap();
         assert_eq!(part_2(input, 10000000), 2511944);
     }
 
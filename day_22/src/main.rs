@@ -1,8 +1,11 @@
+use common::input::InputError;
 use day_22::day_22;
 
-fn main() {
-    let input = day_22::input();
+fn main() -> Result<(), InputError> {
+    let text = common::input::from_args()?;
+    let input = day_22::parse_input(&text)?;
     println!("part 1 => {}", day_22::part_1(input, 10000));
-    let input = day_22::input();
+    let input = day_22::parse_input(&text)?;
     println!("part 2 => {}", day_22::part_2(input, 10000000));
+    Ok(())
 }
@@ -1,156 +1,48 @@
 pub mod day_23 {
+    use common::input::InputError;
+    use common::parse::ParseError;
+    use common::solution::Solution;
+    use std::io::Read;
+    use std::path::Path;
+    use vm::isa::{self, Dialect, Register};
+    use vm::machine::{Effect, Machine};
+    use vm::optimise::optimise;
 
-    pub enum Instruction {
-        Set(char, i32),
-        SetTo(char, char),
-        Sub(char, i32),
-        SubBy(char, char),
-        Mul(char, i32),
-        MulBy(char, char),
-        Jnz(char, i32),
-        JnzExact(i32, i32),
-    }
+    pub use vm::isa::Instruction;
 
-    fn parse(s: &str) -> Instruction {
-        let mut s = s.split_whitespace();
-        match s.next().unwrap() {
-            "sub" => {
-                let register = {
-                    let mut chars = s.next().unwrap().chars();
-                    let result = chars.next().unwrap();
-                    match chars.next() {
-                        Some(i) => panic!("Expected a register, got another char: {}", i),
-                        None => result,
-                    }
-                };
-                let last = s.next().unwrap();
-                match s.next() {
-                    Some(i) => panic!("Expected no more tokens, got {}", i),
-                    None => match last.parse::<i32>() {
-                        Ok(i) => Instruction::Sub(register, i),
-                        Err(_) => {
-                            let mut chars = last.chars();
-                            let target = chars.next().unwrap();
-                            match chars.next() {
-                                Some(i) => panic!(
-                                    "Expected only a one-length register, got another char: {}",
-                                    i
-                                ),
-                                None => Instruction::SubBy(register, target),
-                            }
-                        }
-                    },
-                }
-            }
-            "set" => {
-                let register = {
-                    let mut chars = s.next().unwrap().chars();
-                    let result = chars.next().unwrap();
-                    match chars.next() {
-                        Some(i) => panic!("Expected a register, got another char: {}", i),
-                        None => result,
-                    }
-                };
-                let last = s.next().unwrap();
-                match s.next() {
-                    Some(i) => panic!("Expected no more tokens, got {}", i),
-                    None => match last.parse::<i32>() {
-                        Ok(i) => Instruction::Set(register, i),
-                        Err(_) => {
-                            let mut chars = last.chars();
-                            let target = chars.next().unwrap();
-                            match chars.next() {
-                                Some(i) => panic!(
-                                    "Expected only a one-length register, got another char: {}",
-                                    i
-                                ),
-                                None => Instruction::SetTo(register, target),
-                            }
-                        }
-                    },
-                }
-            }
-            "mul" => {
-                let register = {
-                    let mut chars = s.next().unwrap().chars();
-                    let result = chars.next().unwrap();
-                    match chars.next() {
-                        Some(i) => panic!("Expected a register, got another char: {}", i),
-                        None => result,
-                    }
-                };
-                let last = s.next().unwrap();
-                match s.next() {
-                    Some(i) => panic!("Expected no more tokens, got {}", i),
-                    None => match last.parse::<i32>() {
-                        Ok(i) => Instruction::Mul(register, i),
-                        Err(_) => {
-                            let mut chars = last.chars();
-                            let target = chars.next().unwrap();
-                            match chars.next() {
-                                Some(i) => panic!(
-                                    "Expected only a one-length register, got another char: {}",
-                                    i
-                                ),
-                                None => Instruction::MulBy(register, target),
-                            }
-                        }
-                    },
-                }
-            }
-            "jnz" => {
-                let register = s.next().unwrap();
-                let last = s.next().unwrap().parse::<i32>().unwrap();
-                if let Some(i) = s.next() {
-                    panic!("Expected no more tokens, got {}", i);
-                }
-                match register.parse::<i32>() {
-                    Ok(register) => Instruction::JnzExact(register, last),
-                    Err(_) => {
-                        let mut chars = register.chars();
-                        let result = chars.next().unwrap();
-                        match chars.next() {
-                            Some(i) => panic!("Expected a register, got another char: {}", i),
-                            None => Instruction::Jnz(result, last),
-                        }
-                    }
-                }
-            }
-            instr => panic!("Unrecognised instruction: {}", instr),
-        }
-    }
-
-    pub fn input() -> Vec<Instruction> {
-        let input = include_str!("../input.txt");
-        input
-            .lines()
-            .map(|l| parse(l.trim()))
-            .collect::<Vec<Instruction>>()
+    pub fn parse_input(input: &str) -> Result<Vec<Instruction>, ParseError> {
+        isa::parse_program(Dialect::Coprocessor, input)
     }
 
-    struct Machine<'a> {
-        registers: Vec<i32>,
-        pc: usize,
-        program: &'a [Instruction],
+    pub fn from_reader<R>(reader: R) -> Result<Vec<Instruction>, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
     }
 
-    fn to_index(i: char) -> usize {
-        ((i as u8) - b'a') as usize
+    pub fn from_path<P>(path: P) -> Result<Vec<Instruction>, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
     }
 
     #[allow(dead_code)]
     struct Registers {
-        a: i32,
-        b: i32,
-        c: i32,
-        d: i32,
-        e: i32,
-        f: i32,
-        g: i32,
-        h: i32,
+        a: i64,
+        b: i64,
+        c: i64,
+        d: i64,
+        e: i64,
+        f: i64,
+        g: i64,
+        h: i64,
     }
 
-    fn return_it(registers: &[i32]) -> Registers {
+    fn return_it(registers: &[i64]) -> Registers {
         Registers {
             a: registers[0],
             b: registers[1],
@@ -163,158 +55,67 @@ pub mod day_23 {
         }
     }
 
+    // Yields a snapshot of the registers after each step.
+    struct Steps<'a> {
+        machine: Machine<'a>,
+    }
+
     // Performs one step, and returns true for a `mul` and false otherwise.
-    impl Iterator for Machine<'_> {
+    impl Iterator for Steps<'_> {
         type Item = (bool, Registers);
 
         fn next(&mut self) -> Option<Self::Item> {
-            if self.pc >= self.program.len() {
-                return None;
-            }
-            match self.program[self.pc] {
-                Instruction::Sub(r, i) => {
-                    self.registers[to_index(r)] -= i;
-                    self.pc += 1;
-                    Some((false, return_it(&self.registers)))
-                }
-                Instruction::SubBy(r, s) => {
-                    self.registers[to_index(r)] -= self.registers[to_index(s)];
-                    self.pc += 1;
-                    Some((false, return_it(&self.registers)))
-                }
-                Instruction::Mul(r, i) => {
-                    self.registers[to_index(r)] *= i;
-                    self.pc += 1;
-                    Some((true, return_it(&self.registers)))
-                }
-                Instruction::MulBy(r, s) => {
-                    self.registers[to_index(r)] *= self.registers[to_index(s)];
-                    self.pc += 1;
-                    Some((true, return_it(&self.registers)))
-                }
-                Instruction::Set(r, i) => {
-                    self.registers[to_index(r)] = i;
-                    self.pc += 1;
-                    Some((false, return_it(&self.registers)))
-                }
-                Instruction::SetTo(r, s) => {
-                    self.registers[to_index(r)] = self.registers[to_index(s)];
-                    self.pc += 1;
-                    Some((false, return_it(&self.registers)))
-                }
-                Instruction::Jnz(r, offset) => {
-                    if self.registers[to_index(r)] != 0 {
-                        let target = self.pc as i32 + offset;
-                        if target < 0 || target >= self.program.len() as i32 {
-                            None
-                        } else {
-                            self.pc = target as usize;
-                            Some((false, return_it(&self.registers)))
-                        }
-                    } else {
-                        self.pc += 1;
-                        Some((false, return_it(&self.registers)))
-                    }
-                }
-                Instruction::JnzExact(r, offset) => {
-                    if r != 0 {
-                        let target = self.pc as i32 + offset;
-                        if target < 0 || target >= self.program.len() as i32 {
-                            None
-                        } else {
-                            self.pc = target as usize;
-                            Some((false, return_it(&self.registers)))
-                        }
-                    } else {
-                        self.pc += 1;
-                        Some((false, return_it(&self.registers)))
-                    }
-                }
+            let is_mul = matches!(self.machine.current()?, Instruction::Mul(_, _));
+            match self.machine.step() {
+                Effect::Halted => None,
+                _ => Some((is_mul, return_it(&self.machine.registers))),
             }
         }
     }
 
     pub fn part_1(instructions: &[Instruction]) -> usize {
-        let machine = Machine {
-            registers: vec![0; 8],
-            pc: 0,
-            program: instructions,
+        let machine = Steps {
+            machine: Machine::new(Dialect::Coprocessor, instructions),
         };
         machine.filter(|(i, _)| *i).count()
     }
 
     #[allow(dead_code)]
-    fn part_2_dumb(instructions: &[Instruction]) -> i32 {
-        let mut registers = vec![0; 8];
-        registers[0] = 1;
-        let machine = Machine {
-            registers,
-            pc: 0,
-            program: instructions,
-        };
+    fn part_2_dumb(instructions: &[Instruction]) -> i64 {
+        let mut machine = Machine::new(Dialect::Coprocessor, instructions);
+        machine.set(Register::new('a').unwrap(), 1);
+        let machine = Steps { machine };
         machine.map(|(_, registers)| registers.h).last().unwrap()
     }
 
-    fn is_composite(i: u32) -> bool {
-        if i % 2 == 0 {
-            return i != 2;
-        }
-        if i % 3 == 0 {
-            return i != 3;
-        }
-        let mut factor = 5;
-        while factor * factor <= i {
-            if i % factor == 0 {
-                return true;
-            }
-            factor += 2;
-            if factor * factor <= i && i % factor == 0 {
-                return true;
-            }
-            factor += 4;
-        }
-        false
+    pub fn part_2(instructions: &[Instruction]) -> usize {
+        // Run it for real, but with the hot loops replaced by what they compute.
+        let optimised = optimise(instructions);
+        let mut machine = Machine::new(Dialect::Coprocessor, instructions);
+        machine.set(Register::new('a').unwrap(), 1);
+        optimised.run(&mut machine);
+        machine.get(Register::new('h').unwrap()) as usize
     }
 
-    struct StepUp {
-        value: u32,
-        increment: u32,
-        max: u32,
-    }
+    pub struct Day23;
 
-    fn new_stepup(b: u32, c: u32, step: u32) -> StepUp {
-        StepUp {
-            value: b - step,
-            increment: step,
-            max: c - step,
-        }
-    }
+    impl Solution for Day23 {
+        type Input<'a> = Vec<Instruction>;
+        type Config = ();
+        type Answer1 = usize;
+        type Answer2 = usize;
 
-    impl Iterator for StepUp {
-        type Item = u32;
-        fn next(&mut self) -> Option<Self::Item> {
-            if self.value > self.max {
-                None
-            } else {
-                self.value += self.increment;
-                Some(self.value)
-            }
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
         }
-    }
 
-    pub fn part_2(instructions: &[Instruction]) -> usize {
-        let mut registers = vec![0; 8];
-        registers[0] = 1;
-        let machine = Machine {
-            registers,
-            pc: 0,
-            program: instructions,
-        };
-        let (_, registers) = machine.take(10).last().unwrap();
+        fn part_1(input: &Self::Input<'_>, _: &()) -> usize {
+            part_1(input)
+        }
 
-        new_stepup(registers.b as u32, registers.c as u32, 17)
-            .filter(|i| is_composite(*i))
-            .count()
+        fn part_2(input: &Self::Input<'_>, _: &()) -> usize {
+            part_2(input)
+        }
     }
 }
 
@@ -322,6 +123,19 @@ pub mod day_23 {
 mod tests {
     use super::day_23::*;
 
+    fn input() -> Vec<Instruction> {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse_input("set b 57\nsub z 1").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 5));
+        assert_eq!(error.expected, "a register from 'a' to 'h'");
+        assert_eq!(parse_input("jnz a").unwrap_err().token, None);
+        assert_eq!(parse_input("snd a").unwrap_err().column, 1);
+    }
+
     #[test]
     fn test_day_23() {
         let input = input();
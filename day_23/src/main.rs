@@ -1,7 +1,10 @@
+use common::input::InputError;
 use day_23::day_23;
 
-fn main() {
-    let input = day_23::input();
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let input = day_23::parse_input(&input)?;
     println!("part 1 => {}", day_23::part_1(&input));
     println!("part 2 => {}", day_23::part_2(&input));
+    Ok(())
 }
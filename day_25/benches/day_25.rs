@@ -1,13 +1,34 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use day_25::day_25::{input, part_1};
+use day_25::compact::{Program, Simulator};
+use day_25::day_25::{from_path, part_1, part_1_generic};
 
 fn criterion_benchmark(c: &mut Criterion) {
-    let input = input();
+    let input = from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
     c.bench_function("day 25 part 1", |b| {
         b.iter(|| {
             part_1(&input);
         })
     });
+    c.bench_function("day 25 part 1 generic", |b| {
+        b.iter(|| {
+            part_1_generic(&input);
+        })
+    });
+    let program = Program::new(&input.machine).unwrap();
+    c.bench_function("day 25 part 1 bit tape", |b| {
+        b.iter(|| {
+            let mut simulator = Simulator::new(&program);
+            simulator.run_until(input.pause_after);
+        })
+    });
+    for &size in &[4, 16] {
+        c.bench_function(&format!("day 25 part 1 blocks of {}", size), |b| {
+            b.iter(|| {
+                let mut simulator = Simulator::with_blocks(&program, size);
+                simulator.run_until(input.pause_after);
+            })
+        });
+    }
 }
 
 criterion_group!(benches, criterion_benchmark);
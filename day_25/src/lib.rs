@@ -1,249 +1,1838 @@
-pub mod day_25 {
+pub mod turing {
+    use serde::{Deserialize, Serialize};
+    use std::convert::TryFrom;
 
-    enum Direction {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum Move {
+        #[serde(rename = "L")]
         Left,
+        #[serde(rename = "R")]
         Right,
+        #[serde(rename = "S")]
+        Stay,
+    }
+
+    // What to do on reading a particular symbol from each tape.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Action {
+        pub write: Vec<usize>,
+        pub moves: Vec<Move>,
+        pub next: usize,
+    }
+
+    // Symbols and states are indices into `alphabet` and `states`. Symbol 0
+    // is the blank which every tape starts out full of.
+    #[derive(Debug, Clone)]
+    pub struct Machine {
+        pub alphabet: Vec<String>,
+        pub states: Vec<String>,
+        pub tapes: usize,
+        pub start: usize,
+        // The number of transitions out of each state.
+        width: usize,
+        halting: Vec<bool>,
+        // Indexed by state, then by the symbols under the heads as digits in
+        // base `alphabet.len()`, least significant first. A missing
+        // transition halts the machine.
+        table: Vec<Option<Action>>,
+    }
+
+    // Every state gets a row of the table this long, so don't let a few more
+    // tapes ask for terabytes.
+    pub const MAX_WIDTH: usize = 1 << 16;
+
+    // The number of combinations of `symbols` under `tapes` heads, if it's no
+    // more than `MAX_WIDTH`.
+    pub fn width(symbols: usize, tapes: usize) -> Option<usize> {
+        let tapes = u32::try_from(tapes).ok()?;
+        symbols.checked_pow(tapes).filter(|&w| w <= MAX_WIDTH)
+    }
+
+    impl Machine {
+        pub fn new(alphabet: Vec<String>, tapes: usize) -> Machine {
+            assert!(!alphabet.is_empty(), "Need at least a blank symbol");
+            assert!(tapes > 0, "Need at least one tape");
+            let width = width(alphabet.len(), tapes).expect("Too many tapes for the alphabet");
+            Machine {
+                alphabet,
+                states: vec![],
+                tapes,
+                start: 0,
+                width,
+                halting: vec![],
+                table: vec![],
+            }
+        }
+
+        fn index(&self, state: usize, read: &[usize]) -> usize {
+            let base = self.alphabet.len();
+            let column = read.iter().rev().fold(0, |acc, &s| acc * base + s);
+            state * self.width + column
+        }
+
+        pub fn state(&self, name: &str) -> Option<usize> {
+            self.states.iter().position(|s| s == name)
+        }
+
+        pub fn symbol(&self, name: &str) -> Option<usize> {
+            self.alphabet.iter().position(|s| s == name)
+        }
+
+        // Returns the existing state if there is one by that name.
+        pub fn add_state(&mut self, name: &str) -> usize {
+            if let Some(state) = self.state(name) {
+                return state;
+            }
+            self.states.push(name.to_string());
+            self.halting.push(false);
+            let width = self.width;
+            self.table.extend((0..width).map(|_| None));
+            self.states.len() - 1
+        }
+
+        pub fn set_halting(&mut self, state: usize) {
+            self.halting[state] = true;
+        }
+
+        pub fn is_halting(&self, state: usize) -> bool {
+            self.halting[state]
+        }
+
+        pub fn add_transition(&mut self, state: usize, read: &[usize], action: Action) {
+            assert_eq!(read.len(), self.tapes);
+            assert_eq!(action.write.len(), self.tapes);
+            assert_eq!(action.moves.len(), self.tapes);
+            let index = self.index(state, read);
+            self.table[index] = Some(action);
+        }
+
+        pub fn transition(&self, state: usize, read: &[usize]) -> Option<&Action> {
+            self.table[self.index(state, read)].as_ref()
+        }
+
+        // Runs from blank tapes until `stop` says so or the machine halts.
+        pub fn run_until<F>(&self, stop: F) -> Execution<'_>
+        where
+            F: FnMut(&Execution) -> bool,
+        {
+            let mut execution = Execution::new(self);
+            execution.run_until(stop);
+            execution
+        }
+    }
+
+    // A tape which is blank (symbol 0) everywhere it hasn't been written.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct Tape {
+        // Cell `i` is `positive[i]` if `i >= 0` and `negative[-i - 1]`
+        // otherwise.
+        positive: Vec<usize>,
+        negative: Vec<usize>,
+    }
+
+    impl Tape {
+        pub fn new() -> Tape {
+            Tape::default()
+        }
+
+        pub fn get(&self, position: i64) -> usize {
+            let cell = if position >= 0 {
+                self.positive.get(position as usize)
+            } else {
+                self.negative.get((-position - 1) as usize)
+            };
+            cell.copied().unwrap_or(0)
+        }
+
+        pub fn set(&mut self, position: i64, symbol: usize) {
+            let (half, i) = if position >= 0 {
+                (&mut self.positive, position as usize)
+            } else {
+                (&mut self.negative, (-position - 1) as usize)
+            };
+            if i >= half.len() {
+                if symbol == 0 {
+                    return;
+                }
+                half.resize(i + 1, 0);
+            }
+            half[i] = symbol;
+        }
+
+        // The cells which have ever been written, as a half-open range.
+        pub fn extent(&self) -> (i64, i64) {
+            (-(self.negative.len() as i64), self.positive.len() as i64)
+        }
+
+        pub fn count(&self, symbol: usize) -> usize {
+            self.positive
+                .iter()
+                .chain(self.negative.iter())
+                .filter(|&&s| s == symbol)
+                .count()
+        }
+
+        pub fn non_blank(&self) -> usize {
+            self.positive
+                .iter()
+                .chain(self.negative.iter())
+                .filter(|&&s| s != 0)
+                .count()
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct Execution<'a> {
+        pub machine: &'a Machine,
+        pub state: usize,
+        pub tapes: Vec<Tape>,
+        pub heads: Vec<i64>,
+        pub steps: u64,
+    }
+
+    impl<'a> Execution<'a> {
+        pub fn new(machine: &'a Machine) -> Execution<'a> {
+            Execution {
+                machine,
+                state: machine.start,
+                tapes: vec![Tape::new(); machine.tapes],
+                heads: vec![0; machine.tapes],
+                steps: 0,
+            }
+        }
+
+        pub fn read(&self) -> Vec<usize> {
+            self.tapes
+                .iter()
+                .zip(self.heads.iter())
+                .map(|(tape, &head)| tape.get(head))
+                .collect()
+        }
+
+        fn action(&self) -> Option<&'a Action> {
+            if self.machine.is_halting(self.state) {
+                return None;
+            }
+            self.machine.transition(self.state, &self.read())
+        }
+
+        pub fn halted(&self) -> bool {
+            self.action().is_none()
+        }
+
+        // Returns false, having done nothing, if the machine has halted.
+        pub fn step(&mut self) -> bool {
+            let action = match self.action() {
+                None => return false,
+                Some(action) => action,
+            };
+            for (i, tape) in self.tapes.iter_mut().enumerate() {
+                tape.set(self.heads[i], action.write[i]);
+                match action.moves[i] {
+                    Move::Left => self.heads[i] -= 1,
+                    Move::Right => self.heads[i] += 1,
+                    Move::Stay => {}
+                }
+            }
+            self.state = action.next;
+            self.steps += 1;
+            true
+        }
+
+        // Returns true if `stop` fired, or false if the machine halted first.
+        pub fn run_until<F>(&mut self, mut stop: F) -> bool
+        where
+            F: FnMut(&Execution) -> bool,
+        {
+            while !stop(self) {
+                if !self.step() {
+                    return false;
+                }
+            }
+            true
+        }
+    }
+}
+
+pub mod compact {
+    use crate::turing::{self, Move};
+    use std::collections::HashMap;
+
+    // An unbounded tape of bits, which keeps count of its ones.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct BitTape {
+        // Cell `i` is bit `(i + offset) % 64` of `words[(i + offset) / 64]`.
+        words: Vec<u64>,
+        // Always a multiple of 64.
+        offset: i64,
+        ones: u64,
+    }
+
+    impl BitTape {
+        pub fn new() -> BitTape {
+            BitTape::default()
+        }
+
+        pub fn ones(&self) -> u64 {
+            self.ones
+        }
+
+        // The cells which might be set, as a half-open range.
+        pub fn extent(&self) -> (i64, i64) {
+            (-self.offset, self.words.len() as i64 * 64 - self.offset)
+        }
+
+        fn locate(&self, position: i64) -> Option<(usize, u32)> {
+            let i = position + self.offset;
+            if i < 0 || i >= self.words.len() as i64 * 64 {
+                None
+            } else {
+                Some(((i / 64) as usize, (i % 64) as u32))
+            }
+        }
+
+        // Makes room for `position`, doubling the tape if it has to grow.
+        fn reserve(&mut self, position: i64) {
+            let (start, end) = self.extent();
+            if position >= end {
+                let words = ((position - end) / 64 + 1) as usize;
+                let grown = self.words.len() + words.max(self.words.len());
+                self.words.resize(grown, 0);
+            } else if position < start {
+                let words = ((start - position - 1) / 64 + 1) as usize;
+                let words = words.max(self.words.len());
+                self.words.splice(0..0, std::iter::repeat_n(0, words));
+                self.offset += words as i64 * 64;
+            }
+        }
+
+        pub fn get(&self, position: i64) -> bool {
+            match self.locate(position) {
+                None => false,
+                Some((word, bit)) => self.words[word] & (1 << bit) != 0,
+            }
+        }
+
+        pub fn set(&mut self, position: i64, value: bool) {
+            if self.locate(position).is_none() {
+                if !value {
+                    return;
+                }
+                self.reserve(position);
+            }
+            let (word, bit) = self.locate(position).unwrap();
+            let old = self.words[word];
+            let new = if value {
+                old | (1 << bit)
+            } else {
+                old & !(1 << bit)
+            };
+            self.words[word] = new;
+            self.ones = self.ones + u64::from(new.count_ones()) - u64::from(old.count_ones());
+        }
+
+        // Cells `index * size .. (index + 1) * size`, where `size` divides 64,
+        // with the leftmost cell in the least significant bit.
+        pub fn block(&self, index: i64, size: u32) -> u64 {
+            match self.locate(index * size as i64) {
+                None => 0,
+                Some((word, bit)) => (self.words[word] >> bit) & mask(size),
+            }
+        }
+
+        pub fn set_block(&mut self, index: i64, size: u32, value: u64) {
+            let position = index * size as i64;
+            if self.locate(position).is_none() {
+                if value == 0 {
+                    return;
+                }
+                self.reserve(position);
+            }
+            let (word, bit) = self.locate(position).unwrap();
+            let old = self.words[word];
+            let new = (old & !(mask(size) << bit)) | (value << bit);
+            self.words[word] = new;
+            self.ones = self.ones + u64::from(new.count_ones()) - u64::from(old.count_ones());
+        }
+    }
+
+    fn mask(size: u32) -> u64 {
+        if size == 64 {
+            !0
+        } else {
+            (1 << size) - 1
+        }
     }
 
-    struct Action<Name> {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Action {
         write: bool,
-        direction: Direction,
-        next_state: Name,
+        direction: i64,
+        next: usize,
+    }
+
+    // A single-tape machine over {0, 1}, in a form that's quick to run.
+    pub struct Program {
+        // Indexed by `2 * state + read`; `None` halts.
+        table: Vec<Option<Action>>,
+        start: usize,
     }
 
-    struct Instruction<Name> {
-        state_name: Name,
-        if_zero: Action<Name>,
-        if_one: Action<Name>,
+    impl Program {
+        // Only machines with one tape and two symbols have a compact form.
+        pub fn new(machine: &turing::Machine) -> Option<Program> {
+            if machine.tapes != 1 || machine.alphabet.len() != 2 {
+                return None;
+            }
+            let table = (0..machine.states.len())
+                .flat_map(|state| (0..2).map(move |read| (state, read)))
+                .map(|(state, read)| {
+                    if machine.is_halting(state) {
+                        return None;
+                    }
+                    let action = machine.transition(state, &[read])?;
+                    Some(Action {
+                        write: action.write[0] == 1,
+                        direction: match action.moves[0] {
+                            Move::Left => -1,
+                            Move::Right => 1,
+                            Move::Stay => 0,
+                        },
+                        next: action.next,
+                    })
+                })
+                .collect();
+            Some(Program {
+                table,
+                start: machine.start,
+            })
+        }
+
+        fn states(&self) -> usize {
+            self.table.len() / 2
+        }
+
+        fn action(&self, state: usize, read: bool) -> Option<Action> {
+            self.table[2 * state + read as usize]
+        }
     }
 
+    // The result of running from some position in a block until the head
+    // leaves it.
+    #[derive(Debug, Clone, Copy)]
+    struct Transition {
+        block: u64,
+        state: usize,
+        // The head's new position relative to the start of the block.
+        head: i64,
+        steps: u64,
+        halted: bool,
+    }
+
+    // Memoised runs within a block of `size` cells, keyed by the state, the
+    // head's position in the block, and the block's contents. Filled in as
+    // the machine reaches each configuration: a real run only ever sees a
+    // tiny fraction of the `(states * size) << size` possible ones.
+    struct BlockCache {
+        size: u32,
+        // `None` if the machine never leaves the block.
+        transitions: HashMap<usize, Option<Transition>>,
+    }
+
+    impl BlockCache {
+        fn new(size: u32) -> BlockCache {
+            assert!(
+                size.is_power_of_two() && size <= 16,
+                "Block size must be 1, 2, 4, 8 or 16"
+            );
+            BlockCache {
+                size,
+                transitions: HashMap::new(),
+            }
+        }
+
+        fn compute(
+            program: &Program,
+            size: u32,
+            state: usize,
+            head: i64,
+            block: u64,
+        ) -> Option<Transition> {
+            let (mut state, mut head, mut block) = (state, head, block);
+            // After this many steps we must have repeated a configuration.
+            let limit = (program.states() as u64 * u64::from(size)) << size;
+            let mut steps = 0;
+            while 0 <= head && head < size as i64 {
+                if steps > limit {
+                    return None;
+                }
+                let read = block & (1 << head) != 0;
+                let action = match program.action(state, read) {
+                    None => {
+                        return Some(Transition {
+                            block,
+                            state,
+                            head,
+                            steps,
+                            halted: true,
+                        })
+                    }
+                    Some(action) => action,
+                };
+                if action.write {
+                    block |= 1 << head;
+                } else {
+                    block &= !(1 << head);
+                }
+                head += action.direction;
+                state = action.next;
+                steps += 1;
+            }
+            Some(Transition {
+                block,
+                state,
+                head,
+                steps,
+                halted: false,
+            })
+        }
+
+        fn get(
+            &mut self,
+            program: &Program,
+            state: usize,
+            head: i64,
+            block: u64,
+        ) -> Option<Transition> {
+            let size = self.size;
+            let index = ((state * size as usize + head as usize) << size) | block as usize;
+            *self
+                .transitions
+                .entry(index)
+                .or_insert_with(|| BlockCache::compute(program, size, state, head, block))
+        }
+    }
+
+    pub struct Simulator<'a> {
+        program: &'a Program,
+        pub tape: BitTape,
+        pub head: i64,
+        pub state: usize,
+        pub steps: u64,
+        pub halted: bool,
+        cache: Option<BlockCache>,
+    }
+
+    impl<'a> Simulator<'a> {
+        pub fn new(program: &'a Program) -> Simulator<'a> {
+            Simulator {
+                program,
+                tape: BitTape::new(),
+                head: 0,
+                state: program.start,
+                steps: 0,
+                halted: false,
+                cache: None,
+            }
+        }
+
+        // Runs whole blocks of `size` cells at a time, remembering what
+        // each block did the last time it was in the same configuration.
+        pub fn with_blocks(program: &'a Program, size: u32) -> Simulator<'a> {
+            Simulator {
+                cache: Some(BlockCache::new(size)),
+                ..Simulator::new(program)
+            }
+        }
+
+        pub fn step(&mut self) -> bool {
+            if self.halted {
+                return false;
+            }
+            let action = match self.program.action(self.state, self.tape.get(self.head)) {
+                None => {
+                    self.halted = true;
+                    return false;
+                }
+                Some(action) => action,
+            };
+            self.tape.set(self.head, action.write);
+            self.head += action.direction;
+            self.state = action.next;
+            self.steps += 1;
+            true
+        }
+
+        fn macro_step(&mut self, limit: u64) -> bool {
+            let cache = match self.cache.as_mut() {
+                None => return false,
+                Some(cache) => cache,
+            };
+            let size = cache.size as i64;
+            let index = self.head.div_euclid(size);
+            let block = self.tape.block(index, cache.size);
+            let transition =
+                match cache.get(self.program, self.state, self.head.rem_euclid(size), block) {
+                    Some(t) if self.steps + t.steps <= limit => t,
+                    _ => return false,
+                };
+            self.tape.set_block(index, cache.size, transition.block);
+            self.head = index * size + transition.head;
+            self.state = transition.state;
+            self.steps += transition.steps;
+            self.halted = transition.halted;
+            true
+        }
+
+        // Runs until `limit` steps in total have been taken, or the machine
+        // halts. Returns false if it halted.
+        pub fn run_until(&mut self, limit: u64) -> bool {
+            while self.steps < limit && !self.halted {
+                if !self.macro_step(limit) {
+                    self.step();
+                }
+            }
+            !self.halted
+        }
+    }
+}
+
+pub mod day_25 {
+    use crate::compact::{Program, Simulator};
+    use crate::turing::{self, Action, Move};
+    use common::input::InputError;
+    use common::parse::{ParseError, Token, Tokens};
+    use common::solution::{NoAnswer, Solution};
+    use std::io::Read;
+    use std::path::Path;
+
+    #[derive(Debug)]
     pub struct TuringMachine {
-        transitions: Vec<Instruction<u8>>,
-        pause_after: u32,
-        starting_state: u8,
+        pub machine: turing::Machine,
+        pub pause_after: u64,
     }
 
-    fn extract_string(input: &str, template: &str) -> char {
-        let mut chars = input.chars();
-        for expected in template.chars() {
-            let actual = chars.next().unwrap();
-            if expected != actual {
-                panic!(
-                    "Malformed while matching template, expected: {}, actual: {}",
-                    template, input
-                );
+    // The lines of the input, which remember where they came from.
+    struct Lines<'a> {
+        s: &'a str,
+        pos: usize,
+    }
+
+    impl<'a> Lines<'a> {
+        fn next(&mut self) -> Option<(&'a str, usize)> {
+            if self.pos >= self.s.len() {
+                return None;
             }
+            let start = self.pos;
+            let line = self.s[start..].split('\n').next().unwrap();
+            self.pos += line.len() + 1;
+            Some((line.trim_end_matches('\r'), start))
+        }
+
+        fn peek(&self) -> Option<&'a str> {
+            Lines {
+                s: self.s,
+                pos: self.pos,
+            }
+            .next()
+            .map(|(line, _)| line)
+        }
+
+        fn line<E>(&mut self, expected: E) -> Result<(&'a str, usize), ParseError>
+        where
+            E: Into<String>,
+        {
+            self.next()
+                .ok_or_else(|| ParseError::at_end(self.s, expected))
         }
-        chars.next().unwrap()
     }
 
-    fn extract_word<'a>(input: &'a str, template: &[&str]) -> &'a str {
-        let mut words = input.split_whitespace();
-        for expected in template.iter() {
-            let w = words.next().unwrap();
-            if expected != &w {
-                panic!(
-                    "Expected word in template for input '{}': {}; got: {}",
-                    input, expected, w
-                );
+    // Returns the non-empty name between the template and the terminating
+    // character, and its offset.
+    fn extract_name<'a>(
+        lines: &mut Lines<'a>,
+        template: &str,
+        terminator: char,
+        expected: &str,
+    ) -> Result<Token<'a>, ParseError> {
+        let prefix = format!("'{}'", template.trim());
+        let (line, start) = lines.line(prefix.as_str())?;
+        let mismatch = line
+            .char_indices()
+            .zip(template.chars())
+            .find(|((_, actual), expected)| actual != expected);
+        match mismatch {
+            Some(((i, _), _)) => Err(ParseError::at_char(lines.s, start + i, prefix)),
+            None if line.len() < template.len() => {
+                Err(ParseError::at(lines.s, start + line.len(), None, prefix))
+            }
+            None => {
+                let rest = &line[template.len()..];
+                let offset = start + template.len();
+                match rest.strip_suffix(terminator) {
+                    Some(name) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                        Ok(Token { text: name, offset })
+                    }
+                    _ if rest.is_empty() => Err(ParseError::at(lines.s, offset, None, expected)),
+                    _ => Err(ParseError::at(
+                        lines.s,
+                        offset,
+                        Some(rest),
+                        format!("{} followed by '{}'", expected, terminator),
+                    )),
+                }
             }
         }
-        words.next().unwrap()
     }
 
-    fn peel_action<'a, I, T>(lines: &mut I, state_reduction: fn(char) -> T) -> Action<T>
-    where
-        I: Iterator<Item = &'a str>,
-    {
-        let to_write = match extract_string(lines.next().unwrap(), "    - Write the value ") {
-            '0' => false,
-            '1' => true,
-            s => {
-                panic!("Expected 0 or 1, got: {}", s);
-            }
-        };
-        let to_move = match extract_word(
-            lines.next().unwrap(),
-            &["-", "Move", "one", "slot", "to", "the"],
-        ) {
-            "left." => Direction::Left,
-            "right." => Direction::Right,
-            s => {
-                panic!("Expected a direction, got: {}", s);
-            }
-        };
-        let next_state = extract_string(lines.next().unwrap(), "    - Continue with state ");
-        Action {
-            direction: to_move,
-            next_state: state_reduction(next_state),
-            write: to_write,
+    fn extract_word<'a>(
+        lines: &mut Lines<'a>,
+        template: &[&str],
+        expected: &str,
+    ) -> Result<Token<'a>, ParseError> {
+        let (line, start) = lines.line(format!("'{}'", template.join(" ")))?;
+        let mut words = Tokens::new(line);
+        for w in template.iter() {
+            words.expect(w).map_err(|e| e.shift(lines.s, start))?;
+        }
+        words
+            .token(expected)
+            .map(|t| Token {
+                text: t.text,
+                offset: start + t.offset,
+            })
+            .map_err(|e| e.shift(lines.s, start))
+    }
+
+    struct Parser<'a> {
+        lines: Lines<'a>,
+        // The blank is always "0", so the alphabet can only be fixed once
+        // we've seen the whole input.
+        alphabet: Vec<String>,
+        // States by name, in order of first appearance.
+        states: Vec<&'a str>,
+        // Where each state was defined, and where each was referred to.
+        definitions: Vec<Option<usize>>,
+        references: Vec<(usize, usize)>,
+    }
+
+    impl<'a> Parser<'a> {
+        fn state(&mut self, name: &Token<'a>) -> usize {
+            let state = match self.states.iter().position(|&s| s == name.text) {
+                Some(state) => state,
+                None => {
+                    self.states.push(name.text);
+                    self.definitions.push(None);
+                    self.states.len() - 1
+                }
+            };
+            self.references.push((state, name.offset));
+            state
+        }
+
+        fn symbol(&mut self, name: &str) -> usize {
+            match self.alphabet.iter().position(|s| s == name) {
+                Some(symbol) => symbol,
+                None => {
+                    self.alphabet.push(name.to_string());
+                    self.alphabet.len() - 1
+                }
+            }
+        }
+
+        fn action(&mut self) -> Result<Action, ParseError> {
+            let write = extract_name(&mut self.lines, "    - Write the value ", '.', "a value")?;
+            let write = self.symbol(write.text);
+            let token = extract_word(
+                &mut self.lines,
+                &["-", "Move", "one", "slot", "to", "the"],
+                "a direction",
+            )?;
+            let direction = match token.text {
+                "left." => Move::Left,
+                "right." => Move::Right,
+                _ => {
+                    return Err(ParseError::at(
+                        self.lines.s,
+                        token.offset,
+                        Some(token.text),
+                        "'left.' or 'right.'",
+                    ))
+                }
+            };
+            let next = extract_name(
+                &mut self.lines,
+                "    - Continue with state ",
+                '.',
+                "a state name",
+            )?;
+            Ok(Action {
+                write: vec![write],
+                moves: vec![direction],
+                next: self.state(&next),
+            })
         }
     }
 
     impl TuringMachine {
-        fn parse(s: &str) -> TuringMachine {
-            let mut lines = s.lines();
-            let starting_state = extract_string(&lines.next().unwrap(), "Begin in state ");
-            let pause_after: u32 = extract_word(
-                &lines.next().unwrap(),
+        fn parse(s: &str) -> Result<TuringMachine, ParseError> {
+            let mut parser = Parser {
+                lines: Lines { s, pos: 0 },
+                alphabet: vec!["0".to_string()],
+                states: vec![],
+                definitions: vec![],
+                references: vec![],
+            };
+            let start = extract_name(&mut parser.lines, "Begin in state ", '.', "a state name")?;
+            let start = parser.state(&start);
+            let token = extract_word(
+                &mut parser.lines,
                 &["Perform", "a", "diagnostic", "checksum", "after"],
-            )
-            .parse()
-            .unwrap();
-            let _ = lines.next();
+                "a number of steps",
+            )?;
+            let pause_after: u64 = token.text.parse().map_err(|_| {
+                ParseError::at(s, token.offset, Some(token.text), "a number of steps")
+            })?;
+            let _ = parser.lines.next();
 
             let mut transitions = vec![];
             loop {
-                let state_name = extract_string(lines.next().unwrap(), "In state ");
-                let z = extract_string(lines.next().unwrap(), "  If the current value is ")
-                    .to_digit(10);
-                match z {
-                    Some(0) => {}
-                    s => {
-                        panic!("Expected 0, got: {:?}", s);
-                    }
+                let name = extract_name(&mut parser.lines, "In state ", ':', "a state name")?;
+                let state = parser.state(&name);
+                if parser.definitions[state].is_some() {
+                    return Err(ParseError::at(
+                        s,
+                        name.offset,
+                        Some(name.text),
+                        "a state which hasn't been defined yet",
+                    ));
                 }
-                let if_zero = peel_action(&mut lines, |c| c as u8 - b'A');
-                let z = extract_string(lines.next().unwrap(), "  If the current value is ")
-                    .to_digit(10);
-                match z {
-                    Some(1) => {}
-                    s => {
-                        panic!("Expected 1, got: {:?}", s);
+                parser.definitions[state] = Some(name.offset);
+                let mut read = vec![];
+                loop {
+                    let value = extract_name(
+                        &mut parser.lines,
+                        "  If the current value is ",
+                        ':',
+                        "a value",
+                    )?;
+                    let symbol = parser.symbol(value.text);
+                    if read.contains(&symbol) {
+                        return Err(ParseError::at(
+                            s,
+                            value.offset,
+                            Some(value.text),
+                            "a value which hasn't been handled yet",
+                        ));
+                    }
+                    read.push(symbol);
+                    let action = parser.action()?;
+                    transitions.push((state, symbol, action));
+                    match parser.lines.peek() {
+                        Some(line) if line.starts_with("  If") => {}
+                        _ => break,
                     }
                 }
-                let if_one = peel_action(&mut lines, |c| c as u8 - b'A');
-                transitions.push(Instruction {
-                    state_name: state_name as u8 - b'A',
-                    if_zero,
-                    if_one,
-                });
 
-                match lines.next() {
-                    None => {
-                        transitions.sort_unstable_by_key(|i| i.state_name);
-                        for (pos, t) in transitions.iter().enumerate() {
-                            if pos as u8 != t.state_name {
-                                panic!("Mis-parse! Didn't densely pack the transition array");
-                            }
-                        }
-                        return TuringMachine {
-                            pause_after,
-                            starting_state: starting_state as u8 - b'A',
-                            transitions,
-                        };
-                    }
-                    Some(_) => {}
+                if parser.lines.next().is_none() {
+                    break;
+                }
+            }
+
+            for &(state, offset) in parser.references.iter() {
+                if parser.definitions[state].is_none() {
+                    return Err(ParseError::at(
+                        s,
+                        offset,
+                        Some(parser.states[state]),
+                        "a defined state",
+                    ));
+                }
+            }
+
+            // Number the states in the order they were defined, rather than
+            // the order they were mentioned.
+            let mut order: Vec<usize> = (0..parser.states.len()).collect();
+            order.sort_unstable_by_key(|&state| parser.definitions[state]);
+            let mut renumber = vec![0; order.len()];
+            let mut machine = turing::Machine::new(parser.alphabet, 1);
+            for &state in order.iter() {
+                renumber[state] = machine.add_state(parser.states[state]);
+            }
+            machine.start = renumber[start];
+            for (state, symbol, mut action) in transitions {
+                action.next = renumber[action.next];
+                machine.add_transition(renumber[state], &[symbol], action);
+            }
+            Ok(TuringMachine {
+                machine,
+                pause_after,
+            })
+        }
+    }
+
+    impl TuringMachine {
+        // The inverse of `parse_input`, for machines which the English
+        // description can express.
+        pub fn to_english(&self) -> Result<String, String> {
+            let machine = &self.machine;
+            if machine.tapes != 1 {
+                return Err("The English description only has one tape".to_string());
+            }
+            if machine.alphabet[0] != "0" {
+                return Err("The English description's blank symbol is 0".to_string());
+            }
+            let mut out = format!(
+                "Begin in state {}.\nPerform a diagnostic checksum after {} steps.\n",
+                machine.states[machine.start], self.pause_after
+            );
+            for (state, name) in machine.states.iter().enumerate() {
+                if machine.is_halting(state) {
+                    return Err(format!("State {} halts", name));
+                }
+                if (0..machine.alphabet.len()).all(|s| machine.transition(state, &[s]).is_none()) {
+                    return Err(format!("State {} has no transitions", name));
+                }
+                out.push_str(&format!("\nIn state {}:\n", name));
+                for (symbol, value) in machine.alphabet.iter().enumerate() {
+                    let action = match machine.transition(state, &[symbol]) {
+                        None => continue,
+                        Some(action) => action,
+                    };
+                    let direction = match action.moves[0] {
+                        Move::Left => "left",
+                        Move::Right => "right",
+                        Move::Stay => return Err(format!("State {} doesn't move", name)),
+                    };
+                    out.push_str(&format!(
+                        "  If the current value is {}:\n    - Write the value {}.\n    - Move one slot to the {}.\n    - Continue with state {}.\n",
+                        value, machine.alphabet[action.write[0]], direction, machine.states[action.next]
+                    ));
                 }
             }
+            Ok(out)
         }
     }
 
-    struct ExecutingTuringMachine<'a> {
-        state: u8,
-        tape_positive: Vec<bool>,
-        tape_negative: Vec<bool>,
-        head: i32,
-        spec: &'a TuringMachine,
+    pub fn parse_input(input: &str) -> Result<TuringMachine, ParseError> {
+        TuringMachine::parse(input)
     }
 
-    impl ExecutingTuringMachine<'_> {
-        fn new(t: &TuringMachine) -> ExecutingTuringMachine {
-            ExecutingTuringMachine {
-                state: t.starting_state,
-                tape_positive: vec![],
-                tape_negative: vec![],
-                head: 0,
-                spec: t,
+    pub fn from_reader<R>(reader: R) -> Result<TuringMachine, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<TuringMachine, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
+    }
+
+    // Checked against the bench; the cache only holds the blocks actually seen,
+    // so bigger blocks cost nothing up front.
+    const BLOCK_SIZE: u32 = 16;
+
+    pub fn part_1(tm: &TuringMachine) -> usize {
+        match Program::new(&tm.machine) {
+            Some(program) => {
+                let mut simulator = Simulator::with_blocks(&program, BLOCK_SIZE);
+                simulator.run_until(tm.pause_after);
+                simulator.tape.ones() as usize
             }
+            None => part_1_generic(tm),
         }
+    }
 
-        fn ones(t: &ExecutingTuringMachine) -> usize {
-            t.tape_positive.iter().cloned().filter(|&i| i).count()
-                + t.tape_negative.iter().cloned().filter(|&i| i).count()
+    pub fn part_1_generic(tm: &TuringMachine) -> usize {
+        let execution = tm.machine.run_until(|e| e.steps == tm.pause_after);
+        execution.tapes[0].non_blank()
+    }
+
+    pub struct Day25;
+
+    impl Solution for Day25 {
+        type Input<'a> = TuringMachine;
+        type Config = ();
+        type Answer1 = usize;
+        type Answer2 = NoAnswer;
+
+        const HAS_PART_2: bool = false;
+
+        fn parse(input: &str) -> Result<TuringMachine, ParseError> {
+            parse_input(input)
         }
 
-        fn currently_under_head(t: &ExecutingTuringMachine) -> bool {
-            if t.head >= 0 {
-                let head = t.head as usize;
-                if head >= t.tape_positive.len() {
-                    false
-                } else {
-                    t.tape_positive[head]
+        fn part_1(input: &TuringMachine, _: &()) -> usize {
+            part_1(input)
+        }
+
+        fn part_2(_: &TuringMachine, _: &()) -> NoAnswer {
+            NoAnswer
+        }
+    }
+}
+
+pub mod format {
+    use crate::day_25::TuringMachine;
+    use crate::turing::{self, Action, Move};
+    use common::parse::ParseError;
+    use serde::{Deserialize, Serialize};
+
+    fn letter(i: usize) -> char {
+        (b'A' + i as u8) as char
+    }
+
+    // The busy beaver community's notation, such as `1RB1LC_1RC1RB_...`: for
+    // each state in turn (the first being the start), and for each symbol it
+    // might read, the symbol to write, the direction and the next state.
+    // `---` and states which aren't defined halt.
+    pub fn parse_standard(s: &str) -> Result<turing::Machine, ParseError> {
+        let s = s.trim_end();
+        let rows: Vec<(usize, &str)> = s
+            .split('_')
+            .scan(0, |offset, row| {
+                let start = *offset;
+                *offset += row.len() + 1;
+                Some((start, row))
+            })
+            .collect();
+        if rows.len() > 26 {
+            return Err(ParseError::at_char(s, rows[26].0, "at most 26 states"));
+        }
+        let symbols = rows[0].1.len() / 3;
+        if !(2..=10).contains(&symbols) {
+            return Err(ParseError::at(
+                s,
+                0,
+                Some(rows[0].1),
+                "between 2 and 10 transitions of three characters each",
+            ));
+        }
+        let alphabet = (0..symbols).map(|i| i.to_string()).collect();
+        let mut machine = turing::Machine::new(alphabet, 1);
+        for i in 0..rows.len() {
+            machine.add_state(&letter(i).to_string());
+        }
+        for (state, &(start, row)) in rows.iter().enumerate() {
+            if row.len() != 3 * symbols || !row.is_ascii() {
+                return Err(ParseError::at(
+                    s,
+                    start,
+                    Some(row),
+                    format!("{} transitions of three characters each", symbols),
+                ));
+            }
+            for read in 0..symbols {
+                let offset = start + 3 * read;
+                if &s[offset..offset + 3] == "---" {
+                    continue;
                 }
-            } else {
-                let head = (-t.head - 1) as usize;
-                if head >= t.tape_negative.len() {
-                    false
+                let bytes = s.as_bytes();
+                let write = match (bytes[offset] as char).to_digit(10) {
+                    Some(d) if (d as usize) < symbols => d as usize,
+                    _ => {
+                        return Err(ParseError::at_char(
+                            s,
+                            offset,
+                            format!("a symbol from 0 to {}", symbols - 1),
+                        ))
+                    }
+                };
+                let direction = match bytes[offset + 1] {
+                    b'L' => Move::Left,
+                    b'R' => Move::Right,
+                    _ => return Err(ParseError::at_char(s, offset + 1, "'L' or 'R'")),
+                };
+                let next = match bytes[offset + 2] {
+                    c @ b'A'..=b'Z' => (c - b'A') as usize,
+                    _ => {
+                        return Err(ParseError::at_char(
+                            s,
+                            offset + 2,
+                            "a state from 'A' to 'Z'",
+                        ))
+                    }
+                };
+                let next = if next < rows.len() {
+                    next
                 } else {
-                    t.tape_negative[head]
-                }
+                    let halt = machine.add_state(&letter(next).to_string());
+                    machine.set_halting(halt);
+                    halt
+                };
+                let action = Action {
+                    write: vec![write],
+                    moves: vec![direction],
+                    next,
+                };
+                machine.add_transition(state, &[read], action);
             }
         }
+        Ok(machine)
+    }
 
-        fn set_head(t: &mut ExecutingTuringMachine, value: bool) {
-            if t.head >= 0 {
-                let head = t.head as usize;
-                if head >= t.tape_positive.len() {
-                    t.tape_positive.push(value);
-                } else {
-                    t.tape_positive[head] = value;
+    // Only single-tape machines with at most ten symbols and 25 states (the
+    // last letter is kept for halting) can be written this way. The symbols
+    // are written as their indices, and the states are renamed by letter.
+    pub fn to_standard(machine: &turing::Machine) -> Result<String, String> {
+        if machine.tapes != 1 {
+            return Err("Standard notation only has one tape".to_string());
+        }
+        if machine.alphabet.len() > 10 {
+            return Err("Standard notation has at most ten symbols".to_string());
+        }
+        if machine.is_halting(machine.start) {
+            return Err("The machine halts immediately".to_string());
+        }
+        // The start state comes first.
+        let mut order: Vec<usize> = (0..machine.states.len())
+            .filter(|&s| !machine.is_halting(s))
+            .collect();
+        order.sort_by_key(|&s| s != machine.start);
+        if order.len() > 25 {
+            return Err("Standard notation has at most 25 states".to_string());
+        }
+        // Halting states keep their name if it's a spare letter.
+        let mut letters: Vec<char> = machine
+            .states
+            .iter()
+            .map(|name| match name.as_bytes() {
+                &[c @ b'A'..=b'Z'] if (c - b'A') as usize >= order.len() => c as char,
+                _ => 'Z',
+            })
+            .collect();
+        for (i, &state) in order.iter().enumerate() {
+            letters[state] = letter(i);
+        }
+        let rows: Result<Vec<String>, String> = order
+            .iter()
+            .map(|&state| {
+                let mut row = String::new();
+                for read in 0..machine.alphabet.len() {
+                    match machine.transition(state, &[read]) {
+                        None => row.push_str("---"),
+                        Some(action) => {
+                            row.push_str(&action.write[0].to_string());
+                            row.push(match action.moves[0] {
+                                Move::Left => 'L',
+                                Move::Right => 'R',
+                                Move::Stay => {
+                                    return Err(format!(
+                                        "State {} doesn't move",
+                                        machine.states[state]
+                                    ))
+                                }
+                            });
+                            row.push(letters[action.next]);
+                        }
+                    }
                 }
-            } else {
-                let head = (-t.head - 1) as usize;
-                if head >= t.tape_negative.len() {
-                    t.tape_negative.push(value);
-                } else {
-                    t.tape_negative[head] = value;
+                Ok(row)
+            })
+            .collect();
+        Ok(rows?.join("_"))
+    }
+
+    // A transition table, for JSON and TOML.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Table {
+        // The first symbol is the blank.
+        pub alphabet: Vec<String>,
+        #[serde(default = "one")]
+        pub tapes: usize,
+        pub start: String,
+        #[serde(default)]
+        pub halting: Vec<String>,
+        // How long to run for, as in the puzzle.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub steps: Option<u64>,
+        pub transitions: Vec<Row>,
+    }
+
+    fn one() -> usize {
+        1
+    }
+
+    // One entry per tape in each of `read`, `write` and `moves`.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Row {
+        pub state: String,
+        pub read: Vec<String>,
+        pub write: Vec<String>,
+        pub moves: Vec<Move>,
+        pub next: String,
+    }
+
+    impl Table {
+        pub fn new(tm: &TuringMachine) -> Table {
+            let machine = &tm.machine;
+            let symbols = |s: &[usize]| -> Vec<String> {
+                s.iter().map(|&s| machine.alphabet[s].clone()).collect()
+            };
+            let mut transitions = vec![];
+            for state in 0..machine.states.len() {
+                // Every combination of symbols under the heads, in order.
+                let mut read = vec![0; machine.tapes];
+                loop {
+                    if let Some(action) = machine.transition(state, &read) {
+                        transitions.push(Row {
+                            state: machine.states[state].clone(),
+                            read: symbols(&read),
+                            write: symbols(&action.write),
+                            moves: action.moves.clone(),
+                            next: machine.states[action.next].clone(),
+                        });
+                    }
+                    match read.iter().position(|&s| s + 1 < machine.alphabet.len()) {
+                        None => break,
+                        Some(i) => {
+                            read[i] += 1;
+                            read[..i].iter_mut().for_each(|s| *s = 0);
+                        }
+                    }
                 }
             }
+            Table {
+                alphabet: machine.alphabet.clone(),
+                tapes: machine.tapes,
+                start: machine.states[machine.start].clone(),
+                halting: (0..machine.states.len())
+                    .filter(|&s| machine.is_halting(s))
+                    .map(|s| machine.states[s].clone())
+                    .collect(),
+                steps: Some(tm.pause_after),
+                transitions,
+            }
         }
 
-        fn execute_action(t: &mut ExecutingTuringMachine, action: &Action<u8>) {
-            ExecutingTuringMachine::set_head(t, action.write);
-            match action.direction {
-                Direction::Left => {
-                    t.head -= 1;
+        // States are numbered in the order they first appear. A table has no
+        // positions of its own, so errors point at the start of the document
+        // and name the transition at fault.
+        pub fn to_machine(&self) -> Result<TuringMachine, ParseError> {
+            let error =
+                |token: &str, expected: String| ParseError::at("", 0, Some(token), expected);
+            if self.alphabet.is_empty() {
+                return Err(ParseError::at("", 0, None, "at least one symbol"));
+            }
+            if self.tapes == 0 {
+                return Err(error("0", "at least one tape".to_string()));
+            }
+            if turing::width(self.alphabet.len(), self.tapes).is_none() {
+                return Err(error(
+                    &self.tapes.to_string(),
+                    format!(
+                        "few enough tapes for at most {} combinations of symbols",
+                        turing::MAX_WIDTH
+                    ),
+                ));
+            }
+            let mut machine = turing::Machine::new(self.alphabet.clone(), self.tapes);
+            machine.start = machine.add_state(&self.start);
+            for name in self.halting.iter() {
+                let state = machine.add_state(name);
+                machine.set_halting(state);
+            }
+            for (i, row) in self.transitions.iter().enumerate() {
+                let symbols = |names: &[String]| -> Result<Vec<usize>, ParseError> {
+                    if names.len() != self.tapes {
+                        return Err(error(
+                            &format!("{:?}", names),
+                            format!("{} symbols in transition {}", self.tapes, i),
+                        ));
+                    }
+                    names
+                        .iter()
+                        .map(|n| {
+                            self.alphabet.iter().position(|s| s == n).ok_or_else(|| {
+                                error(n, format!("a symbol from the alphabet in transition {}", i))
+                            })
+                        })
+                        .collect()
+                };
+                let state = machine.add_state(&row.state);
+                let read = symbols(&row.read)?;
+                if machine.transition(state, &read).is_some() {
+                    return Err(error(
+                        &row.state,
+                        format!(
+                            "transition {} not to define a state reading {:?} twice",
+                            i, row.read
+                        ),
+                    ));
                 }
-                Direction::Right => {
-                    t.head += 1;
+                if row.moves.len() != self.tapes {
+                    return Err(error(
+                        &format!("{:?}", row.moves),
+                        format!("{} moves in transition {}", self.tapes, i),
+                    ));
                 }
+                let action = Action {
+                    write: symbols(&row.write)?,
+                    moves: row.moves.clone(),
+                    next: machine.add_state(&row.next),
+                };
+                machine.add_transition(state, &read, action);
             }
-            t.state = action.next_state;
+            Ok(TuringMachine {
+                machine,
+                pause_after: self.steps.unwrap_or(0),
+            })
         }
+    }
+
+    pub fn to_json(tm: &TuringMachine) -> String {
+        serde_json::to_string_pretty(&Table::new(tm)).unwrap()
+    }
+
+    pub fn from_json(s: &str) -> Result<TuringMachine, ParseError> {
+        let table: Table = serde_json::from_str(s).map_err(|e| ParseError {
+            line: e.line().max(1),
+            column: e.column().max(1),
+            token: None,
+            expected: e.to_string(),
+        })?;
+        table.to_machine()
+    }
+
+    pub fn to_toml(tm: &TuringMachine) -> String {
+        toml::to_string(&Table::new(tm)).unwrap()
+    }
+
+    pub fn from_toml(s: &str) -> Result<TuringMachine, ParseError> {
+        let table: Table = toml::from_str(s).map_err(|e| {
+            // TOML counts from zero.
+            let (line, column) = e.line_col().unwrap_or((0, 0));
+            ParseError {
+                line: line + 1,
+                column: column + 1,
+                token: None,
+                expected: e.to_string(),
+            }
+        })?;
+        table.to_machine()
+    }
+}
 
-        fn step(t: &mut ExecutingTuringMachine) {
-            let instruction = &t.spec.transitions[t.state as usize];
-            if ExecutingTuringMachine::currently_under_head(t) {
-                ExecutingTuringMachine::execute_action(t, &instruction.if_one);
+pub mod diagram {
+    use crate::turing::{Execution, Machine};
+    use std::io::{self, Write};
+
+    // Which cells of the first tape to capture.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Window {
+        // `radius` cells either side of the head.
+        Around(usize),
+        // The half-open range `start..end`.
+        Fixed(i64, i64),
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Frame {
+        pub steps: u64,
+        pub state: usize,
+        pub head: i64,
+        // The position of `cells[0]`.
+        pub start: i64,
+        pub cells: Vec<usize>,
+    }
+
+    impl Frame {
+        pub fn new(execution: &Execution, window: Window) -> Frame {
+            let head = execution.heads[0];
+            let (start, end) = match window {
+                Window::Around(radius) => (head - radius as i64, head + radius as i64 + 1),
+                Window::Fixed(start, end) => (start, end),
+            };
+            Frame {
+                steps: execution.steps,
+                state: execution.state,
+                head,
+                start,
+                cells: (start..end).map(|i| execution.tapes[0].get(i)).collect(),
+            }
+        }
+
+        // Where the head is in `cells`, if it's in the window at all.
+        pub fn head_index(&self) -> Option<usize> {
+            let i = self.head - self.start;
+            if 0 <= i && i < self.cells.len() as i64 {
+                Some(i as usize)
             } else {
-                ExecutingTuringMachine::execute_action(t, &instruction.if_zero);
+                None
             }
         }
     }
 
-    pub fn input() -> TuringMachine {
-        let input = include_str!("../input.txt");
-        TuringMachine::parse(&input)
+    // Captures a frame now, and again after every `every` steps, until there
+    // are `count` frames or the machine halts.
+    pub fn capture(
+        execution: &mut Execution,
+        every: u64,
+        count: usize,
+        window: Window,
+    ) -> Vec<Frame> {
+        let mut frames = vec![];
+        while frames.len() < count {
+            frames.push(Frame::new(execution, window));
+            let target = execution.steps + every;
+            if !execution.run_until(|e| e.steps == target) {
+                break;
+            }
+        }
+        frames
     }
 
-    pub fn part_1(tm: &TuringMachine) -> usize {
-        let mut tm = ExecutingTuringMachine::new(&tm);
-        for _ in 0..tm.spec.pause_after {
-            ExecutingTuringMachine::step(&mut tm);
+    fn symbol(machine: &Machine, symbol: usize) -> char {
+        if symbol == 0 {
+            '.'
+        } else {
+            machine.alphabet[symbol].chars().next().unwrap_or('?')
+        }
+    }
+
+    // One line per frame, earliest first: the step count, the state, and the
+    // tape (blank cells as `.`) with the head in brackets.
+    pub fn render_ascii(machine: &Machine, frames: &[Frame]) -> String {
+        let steps_width = frames
+            .iter()
+            .map(|f| f.steps.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let state_width = machine.states.iter().map(|s| s.len()).max().unwrap_or(0);
+        let mut out = String::new();
+        for frame in frames {
+            out.push_str(&format!(
+                "{:>w$} {:<sw$} ",
+                frame.steps,
+                machine.states[frame.state],
+                w = steps_width,
+                sw = state_width
+            ));
+            let head = frame.head_index();
+            for (i, &cell) in frame.cells.iter().enumerate() {
+                out.push(if head == Some(i) {
+                    '['
+                } else if head.is_some_and(|h| h + 1 == i) {
+                    ']'
+                } else {
+                    ' '
+                });
+                out.push(symbol(machine, cell));
+            }
+            out.push(if head.is_some_and(|h| h + 1 == frame.cells.len()) {
+                ']'
+            } else {
+                ' '
+            });
+            out.push('\n');
+        }
+        out
+    }
+
+    fn dimensions(frames: &[Frame]) -> (usize, usize) {
+        let width = frames.iter().map(|f| f.cells.len()).max().unwrap_or(0);
+        (width, frames.len())
+    }
+
+    // A plain PBM: one pixel per cell, black if it isn't blank, and the head
+    // inverted.
+    pub fn write_pbm<W>(frames: &[Frame], mut out: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let (width, height) = dimensions(frames);
+        writeln!(out, "P1\n{} {}", width, height)?;
+        for frame in frames {
+            let head = frame.head_index();
+            let row: Vec<&str> = (0..width)
+                .map(|i| {
+                    let set = frame.cells.get(i).is_some_and(|&c| c != 0);
+                    if set != (head == Some(i)) {
+                        "1"
+                    } else {
+                        "0"
+                    }
+                })
+                .collect();
+            writeln!(out, "{}", row.join(" "))?;
         }
+        Ok(())
+    }
+
+    // Distinct colours for the head in each state, reused if there are lots
+    // of states.
+    const STATE_COLOURS: [[u8; 3]; 8] = [
+        [230, 25, 75],
+        [60, 180, 75],
+        [0, 130, 200],
+        [245, 130, 48],
+        [145, 30, 180],
+        [70, 240, 240],
+        [240, 50, 230],
+        [210, 245, 60],
+    ];
 
-        ExecutingTuringMachine::ones(&tm)
+    // An RGB PNG with each cell `scale` pixels square: blank cells are
+    // white, other symbols are darker shades of grey, and the head is
+    // coloured by its state.
+    pub fn write_png<W>(machine: &Machine, frames: &[Frame], scale: usize, out: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let (width, height) = dimensions(frames);
+        let symbols = machine.alphabet.len().max(2);
+        let mut pixels = Vec::with_capacity(width * height * scale * scale * 3);
+        for frame in frames {
+            let head = frame.head_index();
+            let row: Vec<u8> = (0..width)
+                .flat_map(|i| {
+                    let colour = if head == Some(i) {
+                        STATE_COLOURS[frame.state % STATE_COLOURS.len()]
+                    } else {
+                        let cell = frame.cells.get(i).copied().unwrap_or(0);
+                        let grey = 255 - (255 * cell / (symbols - 1)) as u8;
+                        [grey; 3]
+                    };
+                    std::iter::repeat_n(colour, scale).flatten()
+                })
+                .collect();
+            for _ in 0..scale {
+                pixels.extend_from_slice(&row);
+            }
+        }
+        let mut encoder = png::Encoder::new(out, (width * scale) as u32, (height * scale) as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&pixels).map_err(io::Error::other)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::compact::*;
     use super::day_25::*;
+    use super::diagram::*;
+    use super::format::*;
+    use super::turing::{self, Action, Move};
+
+    fn input() -> TuringMachine {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
 
     #[test]
     fn test_day_25() {
         let input = input();
         assert_eq!(part_1(&input), 2794);
     }
+
+    const EXAMPLE: &str = "Begin in state A.
+Perform a diagnostic checksum after 6 steps.
+
+In state A:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state B.
+  If the current value is 1:
+    - Write the value 0.
+    - Move one slot to the left.
+    - Continue with state B.
+
+In state B:
+  If the current value is 0:
+    - Write the value 1.
+    - Move one slot to the left.
+    - Continue with state A.
+  If the current value is 1:
+    - Write the value 1.
+    - Move one slot to the right.
+    - Continue with state A.
+";
+
+    #[test]
+    fn part1_known() {
+        assert_eq!(part_1(&parse_input(EXAMPLE).unwrap()), 3);
+    }
+
+    #[test]
+    fn test_parse_names() {
+        // Three symbols, and states with longer names.
+        let input = EXAMPLE
+            .replace("state A", "state Start")
+            .replace("state B", "state Other")
+            .replacen("value 1.", "value 2.", 1)
+            .replacen("value is 1", "value is 2", 1);
+        let tm = parse_input(&input).unwrap();
+        assert_eq!(tm.machine.alphabet, ["0", "2", "1"]);
+        assert_eq!(tm.machine.states, ["Start", "Other"]);
+        let other = tm.machine.state("Other").unwrap();
+        let action = tm.machine.transition(tm.machine.start, &[1]).unwrap();
+        assert_eq!(action.next, other);
+        assert!(tm.machine.transition(tm.machine.start, &[2]).is_none());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse_input(&EXAMPLE.replace("with state B", "with state C")).unwrap_err();
+        assert_eq!((error.line, error.column), (8, 27));
+        assert_eq!(error.expected, "a defined state");
+
+        let error = parse_input(&EXAMPLE.replace("In state B", "In state A")).unwrap_err();
+        assert_eq!((error.line, error.token.as_deref()), (14, Some("A")));
+
+        let error = parse_input(&EXAMPLE.replace("the left.", "the up.")).unwrap_err();
+        assert_eq!((error.line, error.column), (11, 28));
+        assert_eq!(error.expected, "'left.' or 'right.'");
+
+        let error = parse_input("Begin in state A").unwrap_err();
+        assert_eq!(error.expected, "a state name followed by '.'");
+    }
+
+    #[test]
+    fn test_run_until() {
+        // Writes "a b a b ..." on the first tape and counts in unary on the
+        // second, until the second tape has three marks.
+        let mut machine =
+            turing::Machine::new(vec![" ".to_string(), "a".to_string(), "b".to_string()], 2);
+        let write_a = machine.add_state("write a");
+        let write_b = machine.add_state("write b");
+        let halt = machine.add_state("halt");
+        machine.set_halting(halt);
+        for &(state, symbol, next) in &[(write_a, 1, write_b), (write_b, 2, write_a)] {
+            machine.add_transition(
+                state,
+                &[0, 0],
+                Action {
+                    write: vec![symbol, 1],
+                    moves: vec![Move::Right, Move::Right],
+                    next,
+                },
+            );
+        }
+        machine.add_transition(
+            write_a,
+            &[0, 1],
+            Action {
+                write: vec![0, 1],
+                moves: vec![Move::Stay, Move::Left],
+                next: halt,
+            },
+        );
+
+        let execution = machine.run_until(|e| e.steps == 2);
+        assert_eq!((execution.heads.clone(), execution.steps), (vec![2, 2], 2));
+        assert_eq!(execution.state, write_a);
+        assert!(!execution.halted());
+
+        let mut execution = turing::Execution::new(&machine);
+        execution.tapes[1].set(2, 1);
+        assert!(!execution.run_until(|_| false));
+        assert_eq!(execution.steps, 3);
+        assert_eq!(execution.state, halt);
+        assert_eq!(execution.heads, [2, 1]);
+        let tape: Vec<usize> = (0..4).map(|i| execution.tapes[0].get(i)).collect();
+        assert_eq!(tape, [1, 2, 0, 0]);
+        assert_eq!(execution.tapes[1].extent(), (0, 3));
+        assert_eq!(execution.tapes[1].count(1), 3);
+    }
+
+    #[test]
+    fn test_bit_tape() {
+        let mut tape = BitTape::new();
+        for &i in &[0, 5, -1, -200, 300] {
+            tape.set(i, true);
+        }
+        tape.set(5, false);
+        tape.set(5, false);
+        tape.set(-1000, false);
+        assert_eq!(tape.ones(), 4);
+        assert!(tape.get(-200) && tape.get(300) && !tape.get(5) && !tape.get(1));
+        let (start, end) = tape.extent();
+        assert!(start <= -200 && end > 300 && start % 64 == 0);
+        assert_eq!(tape.block(-1, 4), 0b1000);
+        tape.set_block(-1, 4, 0b0111);
+        assert_eq!((tape.ones(), tape.get(-1), tape.get(-4)), (6, false, true));
+    }
+
+    // The two-state busy beaver, which halts after 6 steps with 4 ones.
+    fn busy_beaver() -> turing::Machine {
+        let mut machine = turing::Machine::new(vec!["0".to_string(), "1".to_string()], 1);
+        let a = machine.add_state("A");
+        let b = machine.add_state("B");
+        let halt = machine.add_state("H");
+        machine.set_halting(halt);
+        for &(state, read, direction, next) in &[
+            (a, 0, Move::Right, b),
+            (a, 1, Move::Left, b),
+            (b, 0, Move::Left, a),
+            (b, 1, Move::Right, halt),
+        ] {
+            let action = Action {
+                write: vec![1],
+                moves: vec![direction],
+                next,
+            };
+            machine.add_transition(state, &[read], action);
+        }
+        machine
+    }
+
+    #[test]
+    fn test_simulator() {
+        let machine = busy_beaver();
+        let program = Program::new(&machine).unwrap();
+        for &size in &[1, 2, 4, 8, 16] {
+            let mut simulator = Simulator::with_blocks(&program, size);
+            assert!(!simulator.run_until(100));
+            assert_eq!((simulator.steps, simulator.tape.ones()), (6, 4));
+            assert_eq!(simulator.state, machine.state("H").unwrap());
+        }
+
+        // Stopping partway through a block.
+        let input = input();
+        let program = Program::new(&input.machine).unwrap();
+        let mut plain = Simulator::new(&program);
+        let mut blocks = Simulator::with_blocks(&program, 8);
+        for &limit in &[1, 17, 1000, 123_457] {
+            plain.run_until(limit);
+            blocks.run_until(limit);
+            assert_eq!(blocks.steps, limit);
+            assert_eq!(
+                (plain.head, plain.state, &plain.tape),
+                (blocks.head, blocks.state, &blocks.tape)
+            );
+        }
+        assert_eq!(
+            part_1_generic(&parse_input(EXAMPLE).unwrap()),
+            part_1(&parse_input(EXAMPLE).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_english() {
+        let text =
+            common::input::from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
+        assert_eq!(parse_input(&text).unwrap().to_english().unwrap(), text);
+        let example = parse_input(EXAMPLE).unwrap();
+        assert_eq!(example.to_english().unwrap(), EXAMPLE);
+
+        let mut halting = parse_input(EXAMPLE).unwrap();
+        halting.machine.set_halting(1);
+        assert_eq!(halting.to_english(), Err("State B halts".to_string()));
+    }
+
+    #[test]
+    fn test_standard() {
+        // The four-state busy beaver.
+        let machine = parse_standard("1RB1LB_1LA0LC_1RH1LD_1RD0RA").unwrap();
+        assert_eq!(machine.states, ["A", "B", "C", "D", "H"]);
+        assert!(machine.is_halting(4));
+        let program = Program::new(&machine).unwrap();
+        let mut simulator = Simulator::new(&program);
+        assert!(!simulator.run_until(1000));
+        assert_eq!((simulator.steps, simulator.tape.ones()), (107, 13));
+        assert_eq!(
+            to_standard(&machine).unwrap(),
+            "1RB1LB_1LA0LC_1RH1LD_1RD0RA"
+        );
+
+        let example = parse_input(EXAMPLE).unwrap();
+        let standard = to_standard(&example.machine).unwrap();
+        assert_eq!(standard, "1RB0LB_1LA1RA");
+        let machine = parse_standard(&standard).unwrap();
+        assert_eq!(
+            machine.transition(1, &[0]),
+            example.machine.transition(1, &[0])
+        );
+        assert_eq!(
+            parse_standard("1RB---_1LA0RZ").unwrap().transition(0, &[1]),
+            None
+        );
+
+        let error = parse_standard("1RB1LB_1LA0XC").unwrap_err();
+        assert_eq!((error.column, error.expected.as_str()), (12, "'L' or 'R'"));
+        let error = parse_standard("1RB1LB_1LA").unwrap_err();
+        assert_eq!((error.column, error.token.as_deref()), (8, Some("1LA")));
+        assert!(parse_standard("1RB2LB_1LA0LA").is_err());
+    }
+
+    #[test]
+    fn test_table() {
+        let input = input();
+        let json = to_json(&input);
+        let toml = to_toml(&input);
+        assert!(json.contains("\"moves\": [\n        \"R\"\n      ]"));
+        assert!(toml.contains("[[transitions]]\nstate = \"A\""));
+        for tm in &[from_json(&json).unwrap(), from_toml(&toml).unwrap()] {
+            assert_eq!(tm.to_english(), input.to_english());
+            assert_eq!(part_1(tm), 2794);
+        }
+
+        let mut table = Table::new(&input);
+        table.transitions[0].read = vec!["2".to_string()];
+        let error = table.to_machine().unwrap_err();
+        assert_eq!(error.token.as_deref(), Some("2"));
+        assert_eq!(error.expected, "a symbol from the alphabet in transition 0");
+        assert_eq!(from_json("{\"alphabet\": []}").unwrap_err().line, 1);
+        assert_eq!(from_toml("alphabet = []\n[").unwrap_err().line, 2);
+
+        table.transitions[0].read = vec!["0".to_string()];
+        assert!(table.to_machine().is_ok());
+        for &tapes in &[40, 64, 1 << 40] {
+            table.tapes = tapes;
+            let error = table.to_machine().unwrap_err();
+            assert_eq!(error.token, Some(tapes.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_diagram() {
+        let example = parse_input(EXAMPLE).unwrap();
+        let mut execution = turing::Execution::new(&example.machine);
+        let frames = capture(&mut execution, 1, 7, Window::Fixed(-3, 3));
+        let expected = [
+            "0 A  . . .[.]. . ",
+            "1 B  . . . 1[.]. ",
+            "2 A  . . .[1]1 . ",
+            "3 B  . .[.]. 1 . ",
+            "4 A  .[.]1 . 1 . ",
+            "5 B  . 1[1]. 1 . ",
+            "6 A  . 1 1[.]1 . ",
+        ];
+        assert_eq!(
+            render_ascii(&example.machine, &frames),
+            expected.join("\n") + "\n"
+        );
+
+        let mut pbm = vec![];
+        write_pbm(&frames[..2], &mut pbm).unwrap();
+        assert_eq!(
+            String::from_utf8(pbm).unwrap(),
+            "P1\n6 2\n0 0 0 1 0 0\n0 0 0 1 1 0\n"
+        );
+
+        let mut image = vec![];
+        write_png(&example.machine, &frames, 3, &mut image).unwrap();
+        let decoder = png::Decoder::new(&image[..]);
+        let info = decoder.read_info().unwrap().info().clone();
+        assert_eq!((info.width, info.height), (18, 21));
+
+        // Stops when the machine halts, and follows the head.
+        let machine = busy_beaver();
+        let mut execution = turing::Execution::new(&machine);
+        let frames = capture(&mut execution, 4, 10, Window::Around(1));
+        assert_eq!(frames.len(), 2);
+        assert_eq!(
+            (frames[1].steps, frames[1].head, frames[1].start),
+            (4, -2, -3)
+        );
+        assert_eq!(frames[1].cells, [0, 0, 1]);
+    }
 }
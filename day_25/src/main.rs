@@ -1,6 +1,9 @@
+use common::input::InputError;
 use day_25::day_25;
 
-fn main() {
-    let input = day_25::input();
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let input = day_25::parse_input(&input)?;
     println!("part 1 => {}", day_25::part_1(&input));
+    Ok(())
 }
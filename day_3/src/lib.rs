@@ -1,11 +1,31 @@
 pub mod day_3 {
+    use common::input::InputError;
+    use common::parse::{ParseError, Tokens};
+    use common::solution::Solution;
+    use std::io::Read;
+    use std::path::Path;
 
-    pub fn input() -> u32 {
-        let input = include_str!("../input.txt");
-        input
-            .trim()
-            .parse()
-            .unwrap_or_else(|_| panic!("{} wasn't a valid u32", input))
+    pub fn parse_input(input: &str) -> Result<u32, ParseError> {
+        let mut tokens = Tokens::new(input);
+        let n = tokens.parse("a number")?;
+        tokens.end()?;
+        Ok(n)
+    }
+
+    pub fn from_reader<R>(reader: R) -> Result<u32, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<u32, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
     }
 
     fn layer(input: u32) -> u32 {
@@ -61,18 +81,6 @@ pub mod day_3 {
         }
     }
 
-    #[cfg(test)]
-    mod layer_test {
-        use super::*;
-
-        #[test]
-        fn layer_test_1() {
-            assert_eq!(layer_size(1), 1);
-            assert_eq!(layer_size(2), 8);
-            assert_eq!(layer_size(3), 16);
-        }
-    }
-
     #[macro_export]
     macro_rules! set_or_return {
         ( $x:expr, $input:ident, $val: expr) => {{
@@ -239,12 +247,49 @@ pub mod day_3 {
 
         panic!("How could we have broken out of this infinite loop?!")
     }
+
+    pub struct Day3;
+
+    impl Solution for Day3 {
+        type Input<'a> = u32;
+        type Config = ();
+        type Answer1 = u32;
+        type Answer2 = u32;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_1(*input)
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_2(*input)
+        }
+    }
+
+    #[cfg(test)]
+    mod layer_test {
+        use super::*;
+
+        #[test]
+        fn layer_test_1() {
+            assert_eq!(layer_size(1), 1);
+            assert_eq!(layer_size(2), 8);
+            assert_eq!(layer_size(3), 16);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_3::*;
 
+    fn input() -> u32 {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn part1_known() {
         assert_eq!(part_1(1), 0);
@@ -1,14 +1,15 @@
 pub mod day_4 {
+    use common::parse::ParseError;
+    use common::solution::Solution;
 
     use std::collections::HashSet;
     use std::hash::Hash;
 
-    pub fn input() -> Vec<Vec<&'static str>> {
-        let input = include_str!("../input.txt");
-        input
+    pub fn parse_input(input: &str) -> Result<Vec<Vec<&str>>, ParseError> {
+        Ok(input
             .lines()
             .map(|l| l.split_whitespace().collect())
-            .collect()
+            .collect())
     }
 
     fn contains_duplicate<I, X>(i: &mut I) -> bool
@@ -44,16 +45,41 @@ pub mod day_4 {
             })
             .count()
     }
+
+    pub struct Day4;
+
+    impl Solution for Day4 {
+        type Input<'a> = Vec<Vec<&'a str>>;
+        type Config = ();
+        type Answer1 = usize;
+        type Answer2 = usize;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> usize {
+            part_1(input)
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> usize {
+            part_2(input)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_4::*;
 
+    fn raw_input() -> String {
+        common::input::from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn part1_known() {
         assert_eq!(
-            part_1(&vec![
+            part_1(&[
                 vec!["aa", "bb", "cc", "dd", "ee"],
                 vec!["aa", "bb", "cc", "dd", "aa"],
                 vec!["aa", "bb", "cc", "dd", "aaa"]
@@ -65,7 +91,7 @@ mod tests {
     #[test]
     fn part2_known() {
         assert_eq!(
-            part_2(&vec![
+            part_2(&[
                 vec!["abcde", "fghij"],
                 vec!["abcde", "xyz", "ecdab"],
                 vec!["a", "ab", "abc", "abd", "abf", "abj"],
@@ -78,7 +104,8 @@ mod tests {
 
     #[test]
     fn test_day_4() {
-        let input = input();
+        let input = raw_input();
+        let input = parse_input(&input).unwrap();
         assert_eq!(part_1(&input), 325);
         assert_eq!(part_2(&input), 119);
     }
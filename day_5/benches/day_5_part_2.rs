@@ -1,9 +1,9 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use day_5::day_5::{input, part_2};
+use day_5::day_5::{from_path, part_2};
 
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("day 5 part 2", |b| {
-        let input = input();
+        let input = from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
         b.iter(|| {
             let mut input = input.to_vec();
             part_2(&mut input);
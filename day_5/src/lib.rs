@@ -1,18 +1,37 @@
 pub mod day_5 {
+    use common::input::InputError;
+    use common::parse::{self, ParseError, Tokens};
+    use common::solution::Solution;
     use std::convert::TryFrom;
+    use std::io::Read;
+    use std::path::Path;
 
-    pub fn input() -> Vec<i32> {
-        let input = include_str!("../input.txt");
-        input
-            .lines()
-            .map(|l| {
-                l.parse()
-                    .unwrap_or_else(|_| panic!("{} wasn't a valid u32", l))
-            })
-            .collect::<Vec<i32>>()
+    pub fn parse_input(input: &str) -> Result<Vec<i32>, ParseError> {
+        parse::lines(input, |l| {
+            let mut tokens = Tokens::new(l);
+            let offset = tokens.parse("a number")?;
+            tokens.end()?;
+            Ok(offset)
+        })
     }
 
-    pub fn part_1(v: &mut Vec<i32>) -> u32 {
+    pub fn from_reader<R>(reader: R) -> Result<Vec<i32>, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn from_path<P>(path: P) -> Result<Vec<i32>, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
+    }
+
+    pub fn part_1(v: &mut [i32]) -> u32 {
         let mut count = 0;
         let mut index: usize = 0;
         while index < v.len() {
@@ -28,7 +47,7 @@ pub mod day_5 {
         count
     }
 
-    pub fn part_2(v: &mut Vec<i32>) -> u32 {
+    pub fn part_2(v: &mut [i32]) -> u32 {
         let mut count = 0;
         let mut index: usize = 0;
         while index < v.len() {
@@ -47,20 +66,45 @@ pub mod day_5 {
         }
         count
     }
+
+    pub struct Day5;
+
+    impl Solution for Day5 {
+        type Input<'a> = Vec<i32>;
+        type Config = ();
+        type Answer1 = u32;
+        type Answer2 = u32;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_1(&mut input.clone())
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_2(&mut input.clone())
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_5::*;
 
+    fn input() -> Vec<i32> {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn part1_known() {
-        assert_eq!(part_1(&mut vec![0, 3, 0, 1, -3]), 5);
+        assert_eq!(part_1(&mut [0, 3, 0, 1, -3]), 5);
     }
 
     #[test]
     fn part2_known() {
-        assert_eq!(part_2(&mut vec![0, 3, 0, 1, -3]), 10);
+        assert_eq!(part_2(&mut [0, 3, 0, 1, -3]), 10);
     }
 
     #[test]
@@ -0,0 +1,26 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use cycle::cycle::{brent, floyd, hashing, Cycle};
+use cycle::measure::{self, Counting};
+use day_6::day_6::{from_path, redistribute};
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+type Detector = fn(&Vec<u32>, fn(&Vec<u32>) -> Vec<u32>) -> Cycle;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let input = from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
+    let step: fn(&Vec<u32>) -> Vec<u32> = |v| redistribute(v);
+    let detectors: [(&str, Detector); 3] =
+        [("floyd", floyd), ("brent", brent), ("hashing", hashing)];
+    for &(name, detect) in detectors.iter() {
+        let (_, peak) = measure::peak(|| detect(&input, step));
+        println!("day 6 {}: peak memory {} bytes", name, peak);
+        c.bench_function(&format!("day 6 {}", name), |b| {
+            b.iter(|| detect(&input, step))
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,78 +1,90 @@
 pub mod day_6 {
-    use std::collections::HashMap;
-    use std::collections::HashSet;
-
-    pub fn input() -> Vec<u32> {
-        let input = include_str!("../input.txt");
-        input
-            .trim()
-            .split_whitespace()
-            .map(|i| {
-                i.parse()
-                    .unwrap_or_else(|_| panic!("{} wasn't a valid u32", i))
-            })
-            .collect::<Vec<u32>>()
+    use common::input::InputError;
+    use common::parse::{ParseError, Tokens};
+    use common::solution::Solution;
+    use cycle::cycle;
+    use std::io::Read;
+    use std::path::Path;
+
+    pub fn parse_input(input: &str) -> Result<Vec<u32>, ParseError> {
+        let banks = Tokens::new(input).parse_rest("a number")?;
+        if banks.is_empty() {
+            return Err(ParseError::at_end(input, "a number"));
+        }
+        Ok(banks)
+    }
+
+    pub fn from_reader<R>(reader: R) -> Result<Vec<u32>, InputError>
+    where
+        R: Read,
+    {
+        let input = common::input::from_reader(reader)?;
+        Ok(parse_input(&input)?)
     }
 
-    pub fn part_1(v: &mut [u32]) -> u32 {
+    pub fn from_path<P>(path: P) -> Result<Vec<u32>, InputError>
+    where
+        P: AsRef<Path>,
+    {
+        let input = common::input::from_path(path)?;
+        Ok(parse_input(&input)?)
+    }
+
+    // One round of reallocation: empty the fullest bank (the first, if
+    // there's a tie) and deal its blocks out one at a time to the banks after it.
+    pub fn redistribute(v: &[u32]) -> Vec<u32> {
+        if v.is_empty() {
+            return vec![];
+        }
         let len = v.len() as u32;
-        let mut seen: HashSet<Vec<u32>> = HashSet::new();
-        let mut count = 0;
-        let (mut max_pos, mut max) = v
+        let (max_pos, max) = v
             .iter()
             .cloned()
             .enumerate()
+            .rev()
             .max_by_key(|(_, x)| *x)
             .unwrap();
-        while seen.insert(v.to_vec()) {
-            let extras = max % len;
-            let all = max / len;
-            v[max_pos] = 0;
-            for i in 0..(extras as usize) {
-                v[(max_pos + i + 1) % v.len()] += 1;
-            }
-            max = 0;
-            for (i, item) in v.iter_mut().enumerate() {
-                *item += all;
-                if *item > max {
-                    max = *item;
-                    max_pos = i;
-                }
-            }
-            count += 1;
+        let extras = max % len;
+        let all = max / len;
+        let mut v = v.to_vec();
+        v[max_pos] = 0;
+        for i in 0..(extras as usize) {
+            let len = v.len();
+            v[(max_pos + i + 1) % len] += 1;
+        }
+        for item in v.iter_mut() {
+            *item += all;
         }
-        count
+        v
     }
 
-    pub fn part_2(v: &mut [u32]) -> u32 {
-        let len = v.len() as u32;
-        let mut seen: HashMap<Vec<u32>, u32> = HashMap::new();
-        let mut count = 0;
-        let (mut max_pos, mut max) = v
-            .iter()
-            .cloned()
-            .enumerate()
-            .max_by_key(|(_, x)| *x)
-            .unwrap();
-        loop {
-            if let Some(existing) = seen.insert(v.to_vec(), count) {
-                return count - existing;
-            }
-            let extras = max % len;
-            let all = max / len;
-            v[max_pos] = 0;
-            for i in 0..(extras as usize) {
-                v[(max_pos + i + 1) % v.len()] += 1;
-            }
-            max = 0;
-            for (i, item) in v.iter_mut().enumerate() {
-                *item += all;
-                if *item > max {
-                    max = *item;
-                    max_pos = i;
-                }
-            }
-            count += 1;
+    pub fn part_1(v: &[u32]) -> u32 {
+        let cycle = cycle::brent(&v.to_vec(), |v| redistribute(v));
+        (cycle.mu + cycle.lambda) as u32
+    }
+
+    pub fn part_2(v: &[u32]) -> u32 {
+        cycle::brent(&v.to_vec(), |v| redistribute(v)).lambda as u32
+    }
+
+    pub struct Day6;
+
+    impl Solution for Day6 {
+        type Input<'a> = Vec<u32>;
+        type Config = ();
+        type Answer1 = u32;
+        type Answer2 = u32;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_1(input)
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_2(input)
         }
     }
 }
@@ -81,23 +93,40 @@ pub mod day_6 {
 mod tests {
     use super::day_6::*;
 
+    fn input() -> Vec<u32> {
+        from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn part1_known() {
-        assert_eq!(part_1(&mut vec![0, 2, 7, 0]), 5);
+        assert_eq!(part_1(&[0, 2, 7, 0]), 5);
     }
 
     #[test]
     fn part2_known() {
-        assert_eq!(part_2(&mut vec![0, 2, 7, 0]), 4);
+        assert_eq!(part_2(&[0, 2, 7, 0]), 4);
+    }
+
+    #[test]
+    fn test_redistribute() {
+        assert_eq!(redistribute(&[0, 2, 7, 0]), [2, 4, 1, 2]);
+        assert_eq!(redistribute(&[2, 4, 1, 2]), [3, 1, 2, 3]);
+        assert_eq!(redistribute(&[3, 1, 2, 3]), [0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(parse_input("0 2 x").unwrap_err().column, 5);
+        assert_eq!(parse_input("\n").unwrap_err().expected, "a number");
+        assert_eq!(redistribute(&[]), []);
     }
 
     #[test]
     fn test_day_6() {
         let input = input();
-        let answer = part_1(&mut input.clone());
+        let answer = part_1(&input);
         assert_eq!(answer, 4074);
-        let mut input = input;
-        let answer = part_2(&mut input);
+        let answer = part_2(&input);
         assert_eq!(answer, 2793);
     }
 }
@@ -1,11 +1,20 @@
 pub mod day_7 {
+    use common::parse::{self, ParseError};
+    use common::solution::{MaybeAnswer, Solution};
+    use graph::export::{self, Document, Value};
     use std::collections::HashMap;
+    use std::convert::TryFrom;
 
     #[derive(Debug, PartialEq, Eq)]
     pub struct Node<'a> {
         pub(crate) name: &'a str,
         pub(crate) weight: u32,
         pub(crate) children: Vec<&'a str>,
+        // The line this came from, its number, and where each child starts
+        // in it, so that `Tower::new` can point at a problem.
+        pub(crate) line: &'a str,
+        pub(crate) number: usize,
+        pub(crate) offsets: Vec<usize>,
     }
 
     struct TreeNode<'a> {
@@ -28,24 +37,29 @@ pub mod day_7 {
         ExpectSpace,
     }
 
-    pub fn parse_line(s: &str) -> Node {
+    const ARROW: &str = " -> ";
+
+    pub fn parse_line(s: &str) -> Result<Node<'_>, ParseError> {
         let mut state = ParserState::Name;
-        let mut count: usize = 0;
         let mut name_end = 0;
         let mut weight: u32 = 0;
         let mut children: Vec<&str> = vec![];
+        let mut offsets = vec![];
 
-        for chr in s.chars() {
+        for (count, chr) in s.char_indices() {
             match state {
                 ParserState::Name => {
                     if chr == ' ' {
+                        if count == 0 {
+                            return Err(ParseError::at_char(s, count, "a name"));
+                        }
                         state = ParserState::ExpectBracket;
                         name_end = count;
                     }
                 }
                 ParserState::ExpectBracket => {
                     if chr != '(' {
-                        panic!("Malformed string! {}", s)
+                        return Err(ParseError::at_char(s, count, "'('"));
                     }
                     state = ParserState::Weight;
                 }
@@ -53,13 +67,16 @@ pub mod day_7 {
                     if chr == ')' {
                         state = ParserState::AnyChildren(0);
                     } else {
-                        weight = weight * 10
-                            + chr
-                                .to_digit(10)
-                                .unwrap_or_else(|| panic!("Expected a digit, got {}", chr));
+                        weight = chr
+                            .to_digit(10)
+                            .and_then(|d| weight.checked_mul(10)?.checked_add(d))
+                            .ok_or_else(|| ParseError::at_char(s, count, "a digit or ')'"))?;
                     }
                 }
                 ParserState::AnyChildren(i) => {
+                    if !ARROW[i..].starts_with(chr) {
+                        return Err(ParseError::at_char(s, count, format!("'{}'", ARROW)));
+                    }
                     if i < 3 {
                         state = ParserState::AnyChildren(i + 1);
                     } else {
@@ -68,55 +85,96 @@ pub mod day_7 {
                 }
                 ParserState::ParseChild(start) => {
                     if chr == ',' {
+                        if start == count {
+                            return Err(ParseError::at_char(s, count, "a child name"));
+                        }
                         children.push(&s[start..count]);
+                        offsets.push(start);
                         state = ParserState::ExpectSpace;
                     }
                 }
                 ParserState::ExpectSpace => {
                     if chr != ' ' {
-                        panic!("Parse failure, expected space, got {}", chr);
+                        return Err(ParseError::at_char(s, count, "' '"));
                     }
                     state = ParserState::ParseChild(count + 1);
                 }
             }
-            count += 1;
         }
 
         let name = &s[0..name_end];
         match state {
             ParserState::AnyChildren(0) => {
                 // No children
-                Node {
+                Ok(Node {
                     name,
                     weight,
                     children,
-                }
+                    line: s,
+                    number: 1,
+                    offsets,
+                })
             }
-            ParserState::ParseChild(start) => {
-                children.push(&s[start..count]);
-                Node {
+            ParserState::ParseChild(start) if start < s.len() => {
+                children.push(&s[start..]);
+                offsets.push(start);
+                Ok(Node {
                     name,
                     weight,
                     children,
-                }
+                    line: s,
+                    number: 1,
+                    offsets,
+                })
             }
-            _ => {
-                panic!("Parse failure");
+            ParserState::Name => Err(ParseError::at_end(s, "' ('")),
+            ParserState::ExpectBracket => Err(ParseError::at_end(s, "'('")),
+            ParserState::Weight => Err(ParseError::at_end(s, "a digit or ')'")),
+            ParserState::AnyChildren(_) => Err(ParseError::at_end(s, format!("'{}'", ARROW))),
+            ParserState::ParseChild(_) | ParserState::ExpectSpace => {
+                Err(ParseError::at_end(s, "a child name"))
             }
         }
     }
 
-    pub fn input() -> Vec<Node<'static>> {
-        let input = include_str!("../input.txt");
-        input.lines().map(|l| parse_line(l)).collect::<Vec<Node>>()
+    pub fn parse_input(input: &str) -> Result<Vec<Node<'_>>, ParseError> {
+        let mut number = 0;
+        parse::lines(input, |line| {
+            number += 1;
+            Ok(Node {
+                number,
+                ..parse_line(line)?
+            })
+        })
     }
 
-    fn tree_it<'a>(nodes: &[Node<'a>]) -> Tree<'a> {
-        let nodes_by_name: HashMap<&'a str, (&Node<'a>, usize)> = nodes
-            .iter()
-            .enumerate()
-            .map(|(count, i)| (i.name, (i, count)))
-            .collect();
+    fn node_error<E>(node: &Node, offset: usize, token: &str, expected: E) -> ParseError
+    where
+        E: Into<String>,
+    {
+        ParseError {
+            line: node.number,
+            ..ParseError::at(node.line, offset, Some(token), expected)
+        }
+    }
+
+    // Every program must be listed once and held up by at most one other,
+    // and exactly one must be at the bottom.
+    fn tree_it<'a>(nodes: &[Node<'a>]) -> Result<Tree<'a>, ParseError> {
+        if nodes.is_empty() {
+            return Err(ParseError::at_end("", "a program"));
+        }
+        let mut nodes_by_name: HashMap<&'a str, usize> = HashMap::new();
+        for (i, node) in nodes.iter().enumerate() {
+            if nodes_by_name.insert(node.name, i).is_some() {
+                return Err(node_error(
+                    node,
+                    0,
+                    node.name,
+                    "a program not already listed",
+                ));
+            }
+        }
         let mut built: Vec<TreeNode<'a>> = nodes
             .iter()
             .map(|n| TreeNode {
@@ -125,134 +183,297 @@ pub mod day_7 {
                 children: vec![],
             })
             .collect();
-        let mut is_set: Vec<bool> = vec![false; nodes.len()];
-
-        let mut stack: Vec<usize> = vec![];
-        for parent_node_index in 0..nodes.len() {
-            if !is_set[parent_node_index] {
-                stack.push(parent_node_index);
-                while let Some(node_index) = stack.pop() {
-                    if is_set[node_index] {
-                        continue;
-                    }
-                    let node = &nodes[node_index];
-                    let children = (*node).children.iter().map(|&i| {
-                        let (_, child_index) = nodes_by_name.get(i).unwrap();
-                        *child_index
-                    });
-                    built[node_index].children.extend(children);
-                    stack.extend(built[node_index].children.iter());
-                    is_set[node_index] = true;
+        let mut has_parent = vec![false; nodes.len()];
+        for (i, node) in nodes.iter().enumerate() {
+            for (&child, &offset) in node.children.iter().zip(node.offsets.iter()) {
+                let error = |expected| node_error(node, offset, child, expected);
+                let child_index = *nodes_by_name
+                    .get(child)
+                    .ok_or_else(|| error("a program listed in the tower"))?;
+                if has_parent[child_index] {
+                    return Err(error("a program not already held up by another"));
                 }
+                has_parent[child_index] = true;
+                built[i].children.push(child_index);
             }
         }
 
-        // We'll reuse the is_set array for an "is possibly the root" array.
-        // They're all true now.
-        for node in built.iter() {
-            for child in node.children.iter() {
-                is_set[*child] = false;
+        let mut roots = (0..nodes.len()).filter(|&i| !has_parent[i]);
+        let root = match roots.next() {
+            Some(root) => root,
+            None => {
+                return Err(node_error(
+                    &nodes[0],
+                    0,
+                    nodes[0].name,
+                    "a program at the bottom of the tower, not in a cycle",
+                ))
             }
+        };
+        if let Some(other) = roots.next() {
+            return Err(node_error(
+                &nodes[other],
+                0,
+                nodes[other].name,
+                format!(
+                    "a program held up by another, as '{}' is already at the bottom",
+                    nodes[root].name
+                ),
+            ));
         }
-        let (top_node, _) = is_set.iter().enumerate().find(|(_, v)| **v).unwrap();
-        Tree {
-            nodes: built,
-            root: top_node,
-        }
+        Ok(Tree { nodes: built, root })
     }
 
-    pub fn part_1<'a>(nodes: &[Node<'a>]) -> &'a str {
-        let tree = tree_it(nodes);
-        tree.nodes[tree.root].name
+    // The tower, with every program's parent, depth and total weight worked
+    // out up front. Programs are numbered in the order they were listed.
+    pub struct Tower<'a> {
+        nodes: Vec<TreeNode<'a>>,
+        root: usize,
+        indices: HashMap<&'a str, usize>,
+        parents: Vec<Option<usize>>,
+        depths: Vec<usize>,
+        weights: Vec<u32>,
     }
 
-    #[derive(Debug)]
-    struct BalancedWeight {
-        child_weights: u32,
-        self_weight: u32,
-        child_count: usize,
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Imbalance {
+        // The program whose children's towers don't all weigh the same.
+        pub node: usize,
+        // The child whose tower is the odd one out, and how much heavier (or,
+        // if negative, lighter) it is than its siblings'. `None` if there's no
+        // single odd one out, as when there are only two children.
+        pub odd_child: Option<(usize, i64)>,
     }
 
-    fn total_weight(b: &BalancedWeight) -> u32 {
-        b.child_weights * (b.child_count as u32) + b.self_weight
-    }
+    impl<'a> Tower<'a> {
+        pub fn new(input: &[Node<'a>]) -> Result<Tower<'a>, ParseError> {
+            let Tree { nodes, root } = tree_it(input)?;
+            let mut parents = vec![None; nodes.len()];
+            let mut depths = vec![0; nodes.len()];
+            // Breadth-first, so every program comes after its parent.
+            let mut order = vec![root];
+            let mut i = 0;
+            while i < order.len() {
+                let node = order[i];
+                for &child in &nodes[node].children {
+                    parents[child] = Some(node);
+                    depths[child] = depths[node] + 1;
+                    order.push(child);
+                }
+                i += 1;
+            }
+            // With one bottom program and at most one parent each, anything
+            // left over must be going round in a cycle.
+            if let Some(lost) = (0..nodes.len()).find(|&n| n != root && parents[n].is_none()) {
+                return Err(node_error(
+                    &input[lost],
+                    0,
+                    nodes[lost].name,
+                    "a program held up from the bottom of the tower, not in a cycle",
+                ));
+            }
+            let mut weights: Vec<u32> = nodes.iter().map(|n| n.weight).collect();
+            for &node in order.iter().rev() {
+                if let Some(parent) = parents[node] {
+                    weights[parent] =
+                        weights[parent].checked_add(weights[node]).ok_or_else(|| {
+                            node_error(
+                                &input[parent],
+                                0,
+                                nodes[parent].name,
+                                format!("a tower weighing at most {}", u32::MAX),
+                            )
+                        })?;
+                }
+            }
+            let indices = nodes.iter().enumerate().map(|(i, n)| (n.name, i)).collect();
+            Ok(Tower {
+                nodes,
+                root,
+                indices,
+                parents,
+                depths,
+                weights,
+            })
+        }
 
-    fn weight(tree: &Tree, node: usize) -> Result<BalancedWeight, i32> {
-        let node = &tree.nodes[node];
-        let children: Vec<_> = node
-            .children
-            .iter()
-            .map(|node| weight(&tree, *node))
-            .collect();
+        pub fn len(&self) -> usize {
+            self.nodes.len()
+        }
 
-        let count = children.len();
-        let mut c = children.iter();
-        let mut different_child = None;
-        if let Some(child) = c.next() {
-            match child {
-                Err(e) => Err(*e),
-                Ok(child) => {
-                    for other in c {
-                        match other {
-                            Err(e) => return Err(*e),
-                            Ok(other) => {
-                                if total_weight(other) != total_weight(child) {
-                                    match different_child {
-                                        None => {
-                                            different_child = Some(other);
-                                        }
-                                        Some(different_child) => {
-                                            if total_weight(other) == total_weight(different_child)
-                                            {
-                                                // The bad one is `child`.
-                                                return Err(total_weight(other) as i32
-                                                    - (child.child_weights
-                                                        * child.child_count as u32)
-                                                        as i32);
-                                            } else {
-                                                // The bad one is `other`.
-                                                return Err(total_weight(child) as i32
-                                                    - (other.child_weights
-                                                        * other.child_count as u32)
-                                                        as i32);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
+        pub fn is_empty(&self) -> bool {
+            self.nodes.is_empty()
+        }
+
+        pub fn root(&self) -> usize {
+            self.root
+        }
+
+        pub fn index(&self, name: &str) -> Option<usize> {
+            self.indices.get(name).cloned()
+        }
+
+        pub fn name(&self, node: usize) -> &'a str {
+            self.nodes[node].name
+        }
+
+        pub fn weight(&self, node: usize) -> u32 {
+            self.nodes[node].weight
+        }
+
+        pub fn children(&self, node: usize) -> &[usize] {
+            &self.nodes[node].children
+        }
+
+        pub fn parent(&self, node: usize) -> Option<usize> {
+            self.parents[node]
+        }
+
+        // The root is at depth 0.
+        pub fn depth(&self, node: usize) -> usize {
+            self.depths[node]
+        }
+
+        // The weight of this program and everything it holds up.
+        pub fn subtree_weight(&self, node: usize) -> u32 {
+            self.weights[node]
+        }
+
+        pub fn is_balanced(&self, node: usize) -> bool {
+            let mut children = self.children(node).iter().map(|&c| self.weights[c]);
+            match children.next() {
+                None => true,
+                Some(first) => children.all(|w| w == first),
+            }
+        }
+
+        // The child whose tower weighs something different from all its
+        // siblings', which we can only tell if there are at least two siblings
+        // and they all weigh the same.
+        fn odd_child(&self, node: usize) -> Option<(usize, i64)> {
+            let children = self.children(node);
+            children.iter().find_map(|&child| {
+                let weight = self.weights[child];
+                let mut others = children.iter().filter(|&&c| c != child);
+                let expected = self.weights[*others.next()?];
+                let mut count = 1;
+                for &other in others {
+                    if self.weights[other] != expected {
+                        return None;
                     }
-                    match different_child {
-                        None => Ok(BalancedWeight {
-                            self_weight: node.weight,
-                            child_weights: total_weight(child),
-                            child_count: count,
-                        }),
-                        Some(different_child) => {
-                            // The bad one is `different_child`.
-                            Err(total_weight(child) as i32
-                                - (different_child.child_weights
-                                    * different_child.child_count as u32)
-                                    as i32)
+                    count += 1;
+                }
+                if count < 2 || expected == weight {
+                    return None;
+                }
+                Some((child, weight as i64 - expected as i64))
+            })
+        }
+
+        // Every program whose children's towers don't all weigh the same,
+        // nearest the root first. Empty if the whole tower is balanced.
+        pub fn find_imbalances(&self) -> Vec<Imbalance> {
+            let mut imbalances: Vec<Imbalance> = (0..self.len())
+                .filter(|&node| !self.is_balanced(node))
+                .map(|node| Imbalance {
+                    node,
+                    odd_child: self.odd_child(node),
+                })
+                .collect();
+            imbalances.sort_by_key(|i| (self.depths[i.node], i.node));
+            imbalances
+        }
+
+        // The program whose weight is wrong, and what it ought to be: follow
+        // the odd one out down from the root until its own children all agree.
+        pub fn correction(&self) -> Option<(usize, i64)> {
+            let mut node = self.root;
+            let mut found = None;
+            while let Some((child, excess)) = self.odd_child(node) {
+                found = Some((child, self.weight(child) as i64 - excess));
+                node = child;
+            }
+            found
+        }
+
+        // The tower as a graph from each program to the ones it holds up,
+        // with the program whose weight is wrong highlighted.
+        pub fn to_document(&self) -> Document {
+            let correction = self.correction();
+            let nodes = (0..self.len())
+                .map(|i| {
+                    let mut node = export::Node::new(self.name(i));
+                    node.label =
+                        format!("{}\n{} / {}", self.name(i), self.weight(i), self.weights[i]);
+                    node.data
+                        .push(("weight".to_string(), Value::Int(self.weight(i) as i64)));
+                    node.data.push((
+                        "subtree_weight".to_string(),
+                        Value::Int(self.weights[i] as i64),
+                    ));
+                    if let Some((bad, correct)) = correction {
+                        if bad == i {
+                            node.highlight = true;
+                            node.data
+                                .push(("correct_weight".to_string(), Value::Int(correct)));
                         }
                     }
-                }
+                    node
+                })
+                .collect();
+            let edges = (0..self.len())
+                .flat_map(|i| self.children(i).iter().map(move |&c| (i, c)))
+                .collect();
+            Document {
+                name: "tower".to_string(),
+                directed: true,
+                nodes,
+                edges,
             }
-        } else {
-            Ok(BalancedWeight {
-                self_weight: node.weight,
-                child_weights: 0,
-                child_count: 0,
-            })
         }
     }
 
-    pub fn part_2(nodes: &[Node]) -> i32 {
-        let tree = tree_it(nodes);
-        let result = weight(&tree, tree.root);
-        match result {
-            Err(e) => e,
-            Ok(w) => panic!("Expected unbalanced tree, got {:?}", w),
+    pub fn parse_tower(input: &str) -> Result<Tower<'_>, ParseError> {
+        Tower::new(&parse_input(input)?)
+    }
+
+    pub fn part_1<'a>(tower: &Tower<'a>) -> &'a str {
+        tower.name(tower.root())
+    }
+
+    // `None` if there's no single program to blame, as when the tower is
+    // balanced.
+    pub fn part_2(tower: &Tower) -> Option<i32> {
+        let (_, weight) = tower.correction()?;
+        i32::try_from(weight).ok()
+    }
+
+    pub fn to_dot(tower: &Tower) -> String {
+        tower.to_document().to_dot()
+    }
+
+    pub fn to_graphml(tower: &Tower) -> String {
+        tower.to_document().to_graphml()
+    }
+
+    pub struct Day7;
+
+    impl Solution for Day7 {
+        type Input<'a> = Tower<'a>;
+        type Config = ();
+        type Answer1 = String;
+        type Answer2 = MaybeAnswer<i32>;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_tower(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> String {
+            part_1(input).to_string()
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> MaybeAnswer<i32> {
+            MaybeAnswer(part_2(input))
         }
     }
 }
@@ -260,111 +481,233 @@ pub mod day_7 {
 #[cfg(test)]
 mod tests {
     use super::day_7::*;
+    use graph::export::Value;
 
-    fn test_inputs() -> Vec<Node<'static>> {
-        vec![
-            Node {
-                name: "pbga",
-                weight: 66,
-                children: vec![],
-            },
-            Node {
-                name: "xhth",
-                weight: 57,
-                children: vec![],
-            },
-            Node {
-                name: "ebii",
-                weight: 61,
-                children: vec![],
-            },
-            Node {
-                name: "havc",
-                weight: 66,
-                children: vec![],
-            },
-            Node {
-                name: "ktlj",
-                weight: 57,
-                children: vec![],
-            },
-            Node {
-                name: "fwft",
-                weight: 72,
-                children: vec!["ktlj", "cntj", "xhth"],
-            },
-            Node {
-                name: "qoyq",
-                weight: 66,
-                children: vec![],
-            },
-            Node {
-                name: "padx",
-                weight: 45,
-                children: vec!["pbga", "havc", "qoyq"],
-            },
-            Node {
-                name: "tknk",
-                weight: 41,
-                children: vec!["ugml", "padx", "fwft"],
-            },
-            Node {
-                name: "jptl",
-                weight: 61,
-                children: vec![],
-            },
-            Node {
-                name: "ugml",
-                weight: 68,
-                children: vec!["gyxo", "ebii", "jptl"],
-            },
-            Node {
-                name: "gyxo",
-                weight: 61,
-                children: vec![],
-            },
-            Node {
-                name: "cntj",
-                weight: 57,
-                children: vec![],
-            },
-        ]
+    fn raw_input() -> String {
+        common::input::from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
+    const EXAMPLE: &str = "pbga (66)
+xhth (57)
+ebii (61)
+havc (66)
+ktlj (57)
+fwft (72) -> ktlj, cntj, xhth
+qoyq (66)
+padx (45) -> pbga, havc, qoyq
+tknk (41) -> ugml, padx, fwft
+jptl (61)
+ugml (68) -> gyxo, ebii, jptl
+gyxo (61)
+cntj (57)";
+
+    fn tower() -> Tower<'static> {
+        parse_tower(EXAMPLE).unwrap()
     }
 
     #[test]
     fn test_parse() {
         assert_eq!(
             parse_line("vvwrg (51)"),
-            Node {
+            Ok(Node {
                 name: "vvwrg",
                 weight: 51,
-                children: vec![]
-            }
+                children: vec![],
+                line: "vvwrg (51)",
+                number: 1,
+                offsets: vec![],
+            })
         );
         assert_eq!(
             parse_line("uglvj (99) -> ymfjt, gkpgf"),
-            Node {
+            Ok(Node {
                 name: "uglvj",
                 weight: 99,
-                children: vec!["ymfjt", "gkpgf"]
-            }
+                children: vec!["ymfjt", "gkpgf"],
+                line: "uglvj (99) -> ymfjt, gkpgf",
+                number: 1,
+                offsets: vec![14, 21],
+            })
         );
     }
 
+    #[test]
+    fn test_parse_error() {
+        let error = parse_input("vvwrg (51)\nuglvj (9x) -> ymfjt").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 9));
+        assert_eq!(error.token.as_deref(), Some("x"));
+        assert_eq!(error.expected, "a digit or ')'");
+
+        let error = parse_line("uglvj (99) => ymfjt").unwrap_err();
+        assert_eq!(error.column, 12);
+        assert_eq!(parse_line("uglvj (99) -> ymfjt,").unwrap_err().token, None);
+    }
+
     #[test]
     fn part1_known() {
-        assert_eq!(part_1(&test_inputs()), "tknk");
+        assert_eq!(part_1(&tower()), "tknk");
     }
 
     #[test]
     fn part2_known() {
-        assert_eq!(part_2(&test_inputs()), 60);
+        assert_eq!(part_2(&tower()), Some(60));
+    }
+
+    #[test]
+    fn test_tower() {
+        let tower = tower();
+        let node = |name| tower.index(name).unwrap();
+        assert_eq!(tower.len(), 13);
+        assert_eq!(tower.name(tower.root()), "tknk");
+        assert_eq!(tower.parent(tower.root()), None);
+        assert_eq!(tower.parent(node("gyxo")), Some(node("ugml")));
+        let children: Vec<&str> = tower
+            .children(node("padx"))
+            .iter()
+            .map(|&c| tower.name(c))
+            .collect();
+        assert_eq!(children, ["pbga", "havc", "qoyq"]);
+        assert_eq!(
+            (tower.depth(node("tknk")), tower.depth(node("cntj"))),
+            (0, 2)
+        );
+        assert_eq!(tower.weight(node("ugml")), 68);
+        assert_eq!(tower.subtree_weight(node("ugml")), 251);
+        assert_eq!(tower.subtree_weight(node("fwft")), 243);
+        assert_eq!(tower.subtree_weight(node("tknk")), 778);
+        assert_eq!(tower.index("nope"), None);
+
+        assert_eq!(
+            tower.find_imbalances(),
+            vec![Imbalance {
+                node: node("tknk"),
+                odd_child: Some((node("ugml"), 8)),
+            }]
+        );
+        assert_eq!(tower.correction(), Some((node("ugml"), 60)));
+    }
+
+    #[test]
+    fn test_imbalances() {
+        let tower = parse_tower("a (1) -> b, c\nb (2)\nc (2)").unwrap();
+        assert!(tower.find_imbalances().is_empty());
+        assert_eq!(tower.correction(), None);
+        assert_eq!(part_2(&tower), None);
+
+        // With only two children there's no telling which one is wrong.
+        let tower = parse_tower("a (1) -> b, c\nb (2)\nc (3)").unwrap();
+        assert_eq!(
+            tower.find_imbalances(),
+            vec![Imbalance {
+                node: 0,
+                odd_child: None,
+            }]
+        );
+        assert_eq!(tower.correction(), None);
+
+        // Nor when no two of them agree.
+        let tower = parse_tower("a (1) -> b, c, d\nb (5)\nc (6)\nd (7)").unwrap();
+        assert_eq!(
+            tower.find_imbalances(),
+            vec![Imbalance {
+                node: 0,
+                odd_child: None,
+            }]
+        );
+        assert_eq!(part_2(&tower), None);
+
+        let input = raw_input();
+        let tower = parse_tower(&input).unwrap();
+        let imbalances = tower.find_imbalances();
+        assert_eq!(imbalances[0].node, tower.root());
+        // Each imbalance is caused by the one below it.
+        for pair in imbalances.windows(2) {
+            assert_eq!(pair[0].odd_child.map(|(c, _)| c), Some(pair[1].node));
+            assert_eq!(
+                pair[0].odd_child.map(|(_, e)| e),
+                pair[1].odd_child.map(|(_, e)| e)
+            );
+        }
+        let (last, excess) = imbalances.last().unwrap().odd_child.unwrap();
+        assert!(tower.is_balanced(last));
+        assert_eq!(tower.weight(last) as i64 - excess, 1275);
+    }
+
+    #[test]
+    fn test_malformed_tower() {
+        let error = parse_tower("a (1) -> b, zz\nb (1)").err().unwrap();
+        assert_eq!((error.line, error.column), (1, 13));
+        assert_eq!(error.token.as_deref(), Some("zz"));
+        assert_eq!(error.expected, "a program listed in the tower");
+        let error = parse_tower("a (001) -> b, zz\nb (1)").err().unwrap();
+        assert_eq!((error.line, error.column), (1, 15));
+        assert_eq!(parse_input("a (1)\nb (1)").unwrap()[1].number, 2);
+
+        let error = parse_tower("a (1) -> b\nb (1) -> a").err().unwrap();
+        assert_eq!((error.line, error.token.as_deref()), (1, Some("a")));
+        let error = parse_tower("a (1) -> b\nb (1)\nc (1) -> d\nd (1) -> c")
+            .err()
+            .unwrap();
+        assert_eq!((error.line, error.token.as_deref()), (3, Some("c")));
+        let error = parse_tower("a (1)\nb (1)").err().unwrap();
+        assert_eq!(
+            (error.line, error.expected.as_str()),
+            (
+                2,
+                "a program held up by another, as 'a' is already at the bottom"
+            )
+        );
+        let error = parse_tower("a (1) -> c\nb (1) -> c\nc (1)").err().unwrap();
+        assert_eq!((error.line, error.column), (2, 10));
+        assert_eq!(parse_tower("a (1)\na (2)").err().unwrap().line, 2);
+        assert!(parse_tower("").is_err());
+
+        let error = parse_tower("a (1) -> b\nb (4294967295)").err().unwrap();
+        assert_eq!((error.line, error.token.as_deref()), (1, Some("a")));
+    }
+
+    #[test]
+    fn test_to_document() {
+        let document = tower().to_document();
+        let ugml = document.nodes.iter().find(|n| n.id == "ugml").unwrap();
+        assert!(ugml.highlight);
+        assert_eq!(ugml.label, "ugml\n68 / 251");
+        assert_eq!(
+            ugml.data.last(),
+            Some(&("correct_weight".to_string(), Value::Int(60)))
+        );
+        assert_eq!(document.nodes.iter().filter(|n| n.highlight).count(), 1);
+        assert_eq!(document.edges.len(), 12);
+
+        let dot = to_dot(&tower());
+        assert!(dot.starts_with("digraph \"tower\" {\n"));
+        assert!(dot.contains(
+            "    \"ugml\" [label=\"ugml\\n68 / 251\", color=\"red\", penwidth=3, style=\"bold\"];\n"
+        ));
+        assert!(dot.contains("    \"tknk\" [label=\"tknk\\n41 / 778\"];\n"));
+        assert!(dot.contains("    \"tknk\" -> \"ugml\";\n"));
+        let graphml = to_graphml(&tower());
+        assert!(graphml.contains("<data key=\"subtree_weight\">243</data>"));
+
+        let balanced = parse_tower("a (1) -> b, c\nb (2)\nc (2)").unwrap();
+        assert!(balanced.to_document().nodes.iter().all(|n| !n.highlight));
     }
 
     #[test]
     fn test_day_7() {
-        let input = input();
-        assert_eq!(part_1(&input), "dgoocsw");
-        assert_eq!(part_2(&input), 1275);
+        let input = raw_input();
+        let tower = parse_tower(&input).unwrap();
+        assert_eq!(part_1(&tower), "dgoocsw");
+        assert_eq!(part_2(&tower), Some(1275));
+        let culprit = tower
+            .to_document()
+            .nodes
+            .into_iter()
+            .find(|n| n.highlight)
+            .unwrap();
+        assert_eq!(
+            culprit.data.last(),
+            Some(&("correct_weight".to_string(), Value::Int(1275)))
+        );
     }
 }
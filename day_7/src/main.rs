@@ -1,7 +1,29 @@
+use common::input::InputError;
+use common::solution::MaybeAnswer;
 use day_7::day_7;
+use std::process;
 
-fn main() {
-    let input = day_7::input();
-    println!("part 1 => {}", day_7::part_1(&input));
-    println!("part 2 => {}", day_7::part_2(&input));
+const USAGE: &str = "Usage: day_7 [FILE] [dot|graphml]";
+
+fn main() -> Result<(), InputError> {
+    // Optionally `dot` or `graphml` after the input file, to draw the tower
+    // instead of solving the puzzle.
+    let format = std::env::args().nth(2);
+    if let Some(format) = format.as_deref() {
+        if format != "dot" && format != "graphml" {
+            eprintln!("day_7: Expected dot or graphml, got: {}\n{}", format, USAGE);
+            process::exit(2);
+        }
+    }
+    let input = common::input::from_args()?;
+    let tower = day_7::parse_tower(&input)?;
+    match format.as_deref() {
+        Some("dot") => print!("{}", day_7::to_dot(&tower)),
+        Some(_) => print!("{}", day_7::to_graphml(&tower)),
+        None => {
+            println!("part 1 => {}", day_7::part_1(&tower));
+            println!("part 2 => {}", MaybeAnswer(day_7::part_2(&tower)));
+        }
+    }
+    Ok(())
 }
@@ -1,12 +1,16 @@
 pub mod day_8 {
+    use common::parse::{self, ParseError, Tokens};
+    use common::solution::Solution;
 
     use std::collections::HashMap;
 
+    #[derive(Debug)]
     enum Operation {
         Incr,
         Decr,
     }
 
+    #[derive(Debug)]
     enum Comparison {
         Greater,
         Equal,
@@ -16,12 +20,14 @@ pub mod day_8 {
         NotEqual,
     }
 
+    #[derive(Debug)]
     struct Condition<'a> {
         register: &'a str,
         comparison: Comparison,
         number: i32,
     }
 
+    #[derive(Debug)]
     pub struct Instruction<'a> {
         register: &'a str,
         op: Operation,
@@ -29,49 +35,45 @@ pub mod day_8 {
         condition: Condition<'a>,
     }
 
-    pub fn parse(s: &str) -> Instruction {
-        let mut iter = s.split_whitespace();
-        let register = iter.next().unwrap();
-        let op = match iter.next().unwrap() {
+    pub fn parse(s: &str) -> Result<Instruction<'_>, ParseError> {
+        let mut iter = Tokens::new(s);
+        let register = iter.token("a register")?.text;
+        let token = iter.token("'inc' or 'dec'")?;
+        let op = match token.text {
             "inc" => Operation::Incr,
             "dec" => Operation::Decr,
-            s => panic!("Bad! {}", s),
+            _ => return Err(iter.error(&token, "'inc' or 'dec'")),
         };
-        let amount: i32 = iter.next().unwrap().parse().unwrap();
-        let if_clause = iter.next().unwrap();
-        if if_clause != "if" {
-            panic!("Expected 'if', got: {}", if_clause);
-        }
-        let cmp_variable = iter.next().unwrap();
-        let cmp_operator = match iter.next().unwrap() {
+        let amount: i32 = iter.parse("a number")?;
+        iter.expect("if")?;
+        let cmp_variable = iter.token("a register")?.text;
+        let token = iter.token("a comparison")?;
+        let cmp_operator = match token.text {
             "<=" => Comparison::LessEqual,
             ">=" => Comparison::GreaterEqual,
             "==" => Comparison::Equal,
             "<" => Comparison::Less,
             ">" => Comparison::Greater,
             "!=" => Comparison::NotEqual,
-            s => panic!("Expected comparison, got: {}", s),
+            _ => return Err(iter.error(&token, "a comparison")),
         };
-        let cmp_num: i32 = iter.next().unwrap().parse().unwrap();
+        let cmp_num: i32 = iter.parse("a number")?;
         let condition = Condition {
             register: cmp_variable,
             comparison: cmp_operator,
             number: cmp_num,
         };
-        match iter.next() {
-            None => Instruction {
-                register,
-                op,
-                amount,
-                condition,
-            },
-            Some(s) => panic!("Exp ected end of line, got {}", s),
-        }
+        iter.end()?;
+        Ok(Instruction {
+            register,
+            op,
+            amount,
+            condition,
+        })
     }
 
-    pub fn input() -> Vec<Instruction<'static>> {
-        let input = include_str!("../input.txt");
-        input.lines().map(|l| parse(l)).collect::<Vec<_>>()
+    pub fn parse_input(input: &str) -> Result<Vec<Instruction<'_>>, ParseError> {
+        parse::lines(input, parse)
     }
 
     fn process<'a>(instructions: &[Instruction<'a>]) -> HashMap<&'a str, (i32, i32)> {
@@ -102,14 +104,37 @@ pub mod day_8 {
         registers
     }
 
+    // Every register starts at 0, so if none of them was ever changed,
+    // the largest is 0.
     pub fn part_1(instructions: &[Instruction]) -> i32 {
         let map = process(instructions);
-        map.values().map(|&(_, i)| i).max().unwrap()
+        map.values().map(|&(_, i)| i).max().unwrap_or(0)
     }
 
     pub fn part_2(instructions: &[Instruction]) -> i32 {
         let map = process(instructions);
-        map.values().map(|&(i, _)| i).max().unwrap()
+        map.values().map(|&(i, _)| i).max().unwrap_or(0)
+    }
+
+    pub struct Day8;
+
+    impl Solution for Day8 {
+        type Input<'a> = Vec<Instruction<'a>>;
+        type Config = ();
+        type Answer1 = i32;
+        type Answer2 = i32;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> i32 {
+            part_1(input)
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> i32 {
+            part_2(input)
+        }
     }
 }
 
@@ -117,18 +142,40 @@ pub mod day_8 {
 mod tests {
     use super::day_8::*;
 
+    fn raw_input() -> String {
+        common::input::from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     fn data() -> Vec<Instruction<'static>> {
-        vec![
+        [
             "b inc 5 if a > 1",
             "a inc 1 if b < 5",
             "c dec -10 if a >= 1",
             "c inc -20 if c == 10",
         ]
         .iter()
-        .map(|s| parse(s))
+        .map(|s| parse(s).unwrap())
         .collect()
     }
 
+    #[test]
+    fn test_parse_error() {
+        let error = parse_input("b inc 5 if a > 1\na inc 1 if b <> 5").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 14: expected a comparison, got '<>'"
+        );
+        let error = parse_input("a inc 1 if b < 5 else").unwrap_err();
+        assert_eq!(error.expected, "end of line");
+    }
+
+    #[test]
+    fn test_untouched() {
+        assert_eq!(part_1(&[]), 0);
+        let input = parse_input("a inc 1 if b > 1").unwrap();
+        assert_eq!((part_1(&input), part_2(&input)), (0, 0));
+    }
+
     #[test]
     fn part1_known() {
         assert_eq!(part_1(&data()), 1);
@@ -141,7 +188,8 @@ mod tests {
 
     #[test]
     fn test_day_8() {
-        let input = input();
+        let input = raw_input();
+        let input = parse_input(&input).unwrap();
         assert_eq!(part_1(&input), 5752);
         assert_eq!(part_2(&input), 6366);
     }
@@ -1,7 +1,10 @@
+use common::input::InputError;
 use day_8::day_8;
 
-fn main() {
-    let input = day_8::input();
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let input = day_8::parse_input(&input)?;
     println!("part 1 => {}", day_8::part_1(&input));
     println!("part 2 => {}", day_8::part_2(&input));
+    Ok(())
 }
@@ -1,8 +1,10 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use day_9::day_9::{input, part_1, part_2};
+use day_9::day_9::{parse_input, part_1, part_2};
 
 fn criterion_benchmark(c: &mut Criterion) {
-    let input = input();
+    let input =
+        common::input::from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap();
+    let input = parse_input(&input).unwrap();
     c.bench_function("day 9 part 1", |b| {
         b.iter(|| {
             part_1(&input);
@@ -1,4 +1,6 @@
 pub mod day_9 {
+    use common::parse::ParseError;
+    use common::solution::Solution;
 
     #[derive(Debug, Eq, PartialEq)]
     pub(crate) struct GroupIndex {
@@ -16,6 +18,7 @@ pub mod day_9 {
         Group(Group),
     }
 
+    #[derive(Debug)]
     pub struct Stream<'a> {
         pub(crate) groups: Vec<GroupEntry<'a>>,
         pub(crate) head_group: GroupIndex,
@@ -57,13 +60,12 @@ pub mod day_9 {
         }
     }
 
-    pub(crate) fn parse<'a>(s: &'a str) -> Stream<'a> {
-        let mut iter = s.chars().enumerate();
-        match iter.next().unwrap() {
-            (_, '{') => {}
-            (_, c) => {
-                panic!("Group didn't start with open-brace, but '{}'!", c);
-            }
+    pub(crate) fn parse<'a>(s: &'a str) -> Result<Stream<'a>, ParseError> {
+        let mut iter = s.char_indices();
+        match iter.next() {
+            Some((_, '{')) => {}
+            Some((pos, _)) => return Err(ParseError::at_char(s, pos, "'{'")),
+            None => return Err(ParseError::at_end(s, "'{'")),
         }
         let mut groups: Vec<GroupEntry<'a>> = vec![];
         let mut stack: Vec<Vec<GroupIndex>> = vec![vec![]];
@@ -72,6 +74,10 @@ pub mod day_9 {
         let mut skip = false;
 
         for (pos, c) in iter {
+            if stack.is_empty() {
+                // The outermost group has already been closed.
+                return Err(ParseError::at_char(s, pos, "end of input"));
+            }
             if let Some(start) = garbage {
                 if skip {
                     skip = false;
@@ -114,22 +120,28 @@ pub mod day_9 {
                         }
                     }
                     ',' => {}
-                    c => {
-                        panic!("Expected a known character, got: {}", c);
+                    _ => {
+                        return Err(ParseError::at_char(s, pos, "'{', '}', '<' or ','"));
                     }
                 }
             }
         }
+        if garbage.is_some() {
+            return Err(ParseError::at_end(s, "'>'"));
+        }
+        if !stack.is_empty() {
+            return Err(ParseError::at_end(s, "'}'"));
+        }
         let max = groups.len() - 1;
-        Stream {
+        Ok(Stream {
             groups,
             head_group: GroupIndex { i: max },
-        }
+        })
     }
 
-    pub fn input() -> Stream<'static> {
-        let input = include_str!("../input.txt");
-        parse(input.trim())
+    pub fn parse_input(input: &str) -> Result<Stream<'_>, ParseError> {
+        let start = input.len() - input.trim_start().len();
+        parse(input.trim()).map_err(|e| e.shift(input, start))
     }
 
     pub fn part_1(numbers: &Stream) -> usize {
@@ -177,15 +189,40 @@ pub mod day_9 {
             },
         )
     }
+
+    pub struct Day9;
+
+    impl Solution for Day9 {
+        type Input<'a> = Stream<'a>;
+        type Config = ();
+        type Answer1 = usize;
+        type Answer2 = u32;
+
+        fn parse(input: &str) -> Result<Self::Input<'_>, ParseError> {
+            parse_input(input)
+        }
+
+        fn part_1(input: &Self::Input<'_>, _: &()) -> usize {
+            part_1(input)
+        }
+
+        fn part_2(input: &Self::Input<'_>, _: &()) -> u32 {
+            part_2(input)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::day_9::*;
 
+    fn raw_input() -> String {
+        common::input::from_path(concat!(env!("CARGO_MANIFEST_DIR"), "/input.txt")).unwrap()
+    }
+
     #[test]
     fn test_empty_parse() {
-        let result = parse("{}");
+        let result = parse("{}").unwrap();
         assert_eq!(result.groups.len(), 1);
         match &result.groups[0] {
             GroupEntry::Group(Group { entries: e }) => {
@@ -200,7 +237,7 @@ mod tests {
 
     #[test]
     fn test_triple_empty_parse() {
-        let result = parse("{{{}}}");
+        let result = parse("{{{}}}").unwrap();
         assert_eq!(result.groups.len(), 3);
         let (zero_count, one_count) = Stream::cata(
             &result,
@@ -226,21 +263,31 @@ mod tests {
         assert_eq!(one_count, 2);
     }
 
+    #[test]
+    fn test_parse_error() {
+        assert_eq!(parse_input("{{}").unwrap_err().expected, "'}'");
+        assert_eq!(parse_input("{<a}").unwrap_err().expected, "'>'");
+        let error = parse_input("\n{{},x}").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 5));
+        assert_eq!(parse_input("{}}").unwrap_err().column, 3);
+    }
+
     #[test]
     fn part_1_known() {
-        assert_eq!(part_1(&parse("{}")), 1);
-        assert_eq!(part_1(&parse("{{{}}}")), 6);
-        assert_eq!(part_1(&parse("{{},{}}")), 5);
-        assert_eq!(part_1(&parse("{{{},{},{{}}}}")), 16);
-        assert_eq!(part_1(&parse("{<a>,<a>,<a>,<a>}")), 1);
-        assert_eq!(part_1(&parse("{{<ab>},{<ab>},{<ab>},{<ab>}}")), 9);
-        assert_eq!(part_1(&parse("{{<!!>},{<!!>},{<!!>},{<!!>}}")), 9);
-        assert_eq!(part_1(&parse("{{<a!>},{<a!>},{<a!>},{<ab>}}")), 3);
+        assert_eq!(part_1(&parse("{}").unwrap()), 1);
+        assert_eq!(part_1(&parse("{{{}}}").unwrap()), 6);
+        assert_eq!(part_1(&parse("{{},{}}").unwrap()), 5);
+        assert_eq!(part_1(&parse("{{{},{},{{}}}}").unwrap()), 16);
+        assert_eq!(part_1(&parse("{<a>,<a>,<a>,<a>}").unwrap()), 1);
+        assert_eq!(part_1(&parse("{{<ab>},{<ab>},{<ab>},{<ab>}}").unwrap()), 9);
+        assert_eq!(part_1(&parse("{{<!!>},{<!!>},{<!!>},{<!!>}}").unwrap()), 9);
+        assert_eq!(part_1(&parse("{{<a!>},{<a!>},{<a!>},{<ab>}}").unwrap()), 3);
     }
 
     #[test]
     fn test_day_9() {
-        let input = input();
+        let input = raw_input();
+        let input = parse_input(&input).unwrap();
         assert_eq!(part_1(&input), 16869);
         assert_eq!(part_2(&input), 7284);
     }
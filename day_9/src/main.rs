@@ -1,7 +1,10 @@
+use common::input::InputError;
 use day_9::day_9;
 
-fn main() {
-    let input = day_9::input();
+fn main() -> Result<(), InputError> {
+    let input = common::input::from_args()?;
+    let input = day_9::parse_input(&input)?;
     println!("part 1 => {}", day_9::part_1(&input));
     println!("part 2 => {}", day_9::part_2(&input));
+    Ok(())
 }
@@ -0,0 +1,710 @@
+pub mod union_find {
+    // Disjoint sets over 0..n, with path halving and union by size.
+    pub struct UnionFind {
+        parent: Vec<usize>,
+        size: Vec<usize>,
+        sets: usize,
+    }
+
+    impl UnionFind {
+        pub fn new(n: usize) -> UnionFind {
+            UnionFind {
+                parent: (0..n).collect(),
+                size: vec![1; n],
+                sets: n,
+            }
+        }
+
+        pub fn find(&mut self, mut x: usize) -> usize {
+            while self.parent[x] != x {
+                self.parent[x] = self.parent[self.parent[x]];
+                x = self.parent[x];
+            }
+            x
+        }
+
+        // Returns false if the two were already in the same set.
+        pub fn union(&mut self, a: usize, b: usize) -> bool {
+            let (mut a, mut b) = (self.find(a), self.find(b));
+            if a == b {
+                return false;
+            }
+            if self.size[a] < self.size[b] {
+                std::mem::swap(&mut a, &mut b);
+            }
+            self.parent[b] = a;
+            self.size[a] += self.size[b];
+            self.sets -= 1;
+            true
+        }
+
+        pub fn same(&mut self, a: usize, b: usize) -> bool {
+            self.find(a) == self.find(b)
+        }
+
+        pub fn size_of(&mut self, x: usize) -> usize {
+            let root = self.find(x);
+            self.size[root]
+        }
+
+        pub fn sets(&self) -> usize {
+            self.sets
+        }
+    }
+}
+
+pub mod graph {
+    use crate::export::{self, Document, Value, PALETTE};
+    use crate::union_find::UnionFind;
+    use common::parse::{self, ParseError, Tokens};
+    use std::collections::{HashMap, VecDeque};
+
+    // An undirected graph with named nodes, stored as adjacency lists.
+    // Nodes are numbered in the order they're first mentioned.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct Graph {
+        names: Vec<String>,
+        indices: HashMap<String, usize>,
+        neighbours: Vec<Vec<usize>>,
+    }
+
+    impl Graph {
+        pub fn new() -> Graph {
+            Graph::default()
+        }
+
+        pub fn len(&self) -> usize {
+            self.names.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.names.is_empty()
+        }
+
+        // Returns the existing node if there is one by that name.
+        pub fn add_node(&mut self, name: &str) -> usize {
+            if let Some(&i) = self.indices.get(name) {
+                return i;
+            }
+            let i = self.names.len();
+            self.names.push(name.to_string());
+            self.indices.insert(name.to_string(), i);
+            self.neighbours.push(vec![]);
+            i
+        }
+
+        // Adding an edge that's already there does nothing, so each edge can
+        // be listed from both of its ends.
+        pub fn add_edge(&mut self, a: usize, b: usize) {
+            if !self.neighbours[a].contains(&b) {
+                self.neighbours[a].push(b);
+                if a != b {
+                    self.neighbours[b].push(a);
+                }
+            }
+        }
+
+        pub fn index(&self, name: &str) -> Option<usize> {
+            self.indices.get(name).cloned()
+        }
+
+        pub fn name(&self, node: usize) -> &str {
+            &self.names[node]
+        }
+
+        pub fn names(&self) -> &[String] {
+            &self.names
+        }
+
+        pub fn neighbours(&self, node: usize) -> &[usize] {
+            &self.neighbours[node]
+        }
+
+        pub fn degree(&self, node: usize) -> usize {
+            self.neighbours[node].len()
+        }
+
+        // Each edge once, with the lower-numbered end first.
+        pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+            self.neighbours
+                .iter()
+                .enumerate()
+                .flat_map(|(a, ns)| ns.iter().filter(move |&&b| a <= b).map(move |&b| (a, b)))
+        }
+
+        pub fn edge_count(&self) -> usize {
+            self.edges().count()
+        }
+
+        pub fn components(&self) -> Components {
+            let mut sets = UnionFind::new(self.len());
+            for (a, b) in self.edges() {
+                sets.union(a, b);
+            }
+            let mut labels = vec![0; self.len()];
+            let mut sizes = vec![];
+            let mut by_root = HashMap::new();
+            for (node, label) in labels.iter_mut().enumerate() {
+                let root = sets.find(node);
+                *label = *by_root.entry(root).or_insert_with(|| {
+                    sizes.push(sets.size_of(root));
+                    sizes.len() - 1
+                });
+            }
+            Components { labels, sizes }
+        }
+
+        // The number of edges from `from` to every node, or `None` for a
+        // node that can't be reached.
+        pub fn distances(&self, from: usize) -> Vec<Option<usize>> {
+            let mut distances = vec![None; self.len()];
+            distances[from] = Some(0);
+            let mut queue = VecDeque::new();
+            queue.push_back(from);
+            while let Some(node) = queue.pop_front() {
+                let next = distances[node].map(|d| d + 1);
+                for &n in &self.neighbours[node] {
+                    if distances[n].is_none() {
+                        distances[n] = next;
+                        queue.push_back(n);
+                    }
+                }
+            }
+            distances
+        }
+
+        // One of the shortest paths, including both ends.
+        pub fn shortest_path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+            let mut previous = vec![None; self.len()];
+            previous[from] = Some(from);
+            let mut queue = VecDeque::new();
+            queue.push_back(from);
+            while let Some(node) = queue.pop_front() {
+                if node == to {
+                    let mut path = vec![to];
+                    let mut node = to;
+                    while node != from {
+                        node = previous[node].unwrap();
+                        path.push(node);
+                    }
+                    path.reverse();
+                    return Some(path);
+                }
+                for &n in &self.neighbours[node] {
+                    if previous[n].is_none() {
+                        previous[n] = Some(node);
+                        queue.push_back(n);
+                    }
+                }
+            }
+            None
+        }
+
+        pub fn degrees(&self) -> Degrees {
+            let mut histogram = vec![];
+            for node in 0..self.len() {
+                let degree = self.degree(node);
+                if histogram.len() <= degree {
+                    histogram.resize(degree + 1, 0);
+                }
+                histogram[degree] += 1;
+            }
+            Degrees { histogram }
+        }
+    }
+
+    impl Graph {
+        // Each node filled with a colour for its component, which is also
+        // recorded exactly in case there are more components than colours.
+        pub fn to_document(&self, name: &str) -> Document {
+            let components = self.components();
+            let nodes = (0..self.len())
+                .map(|i| {
+                    let component = components.component_of(i);
+                    let mut node = export::Node::new(self.name(i));
+                    node.data
+                        .push(("component".to_string(), Value::Int(component as i64)));
+                    node.fill = Some(PALETTE[component % PALETTE.len()].to_string());
+                    node
+                })
+                .collect();
+            Document {
+                name: name.to_string(),
+                directed: false,
+                nodes,
+                edges: self.edges().collect(),
+            }
+        }
+    }
+
+    // Components are numbered in the order of their lowest-numbered node.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Components {
+        labels: Vec<usize>,
+        sizes: Vec<usize>,
+    }
+
+    impl Components {
+        pub fn count(&self) -> usize {
+            self.sizes.len()
+        }
+
+        pub fn component_of(&self, node: usize) -> usize {
+            self.labels[node]
+        }
+
+        pub fn sizes(&self) -> &[usize] {
+            &self.sizes
+        }
+
+        pub fn size_of(&self, node: usize) -> usize {
+            self.sizes[self.labels[node]]
+        }
+
+        pub fn members(&self, component: usize) -> impl Iterator<Item = usize> + '_ {
+            self.labels
+                .iter()
+                .enumerate()
+                .filter(move |(_, &l)| l == component)
+                .map(|(node, _)| node)
+        }
+    }
+
+    // `histogram[d]` is how many nodes have degree `d`. A self-loop counts
+    // once towards its node's degree.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Degrees {
+        pub histogram: Vec<usize>,
+    }
+
+    impl Degrees {
+        pub fn min(&self) -> Option<usize> {
+            self.histogram.iter().position(|&n| n > 0)
+        }
+
+        pub fn max(&self) -> Option<usize> {
+            self.histogram.iter().rposition(|&n| n > 0)
+        }
+
+        pub fn mean(&self) -> Option<f64> {
+            let nodes: usize = self.histogram.iter().sum();
+            if nodes == 0 {
+                return None;
+            }
+            let total: usize = self.histogram.iter().enumerate().map(|(d, n)| d * n).sum();
+            Some(total as f64 / nodes as f64)
+        }
+    }
+
+    // One line of `a <-> b, c`: a node, then everything it's connected to.
+    pub fn parse_line(s: &str) -> Result<(&str, Vec<&str>), ParseError> {
+        let mut iter = Tokens::new(s);
+        let node = iter.token("a node name")?.text;
+        iter.expect("<->")?;
+
+        // Neighbours are separated by a comma straight after each name.
+        let mut neighbours = vec![];
+        let mut separated = true;
+        while let Some(token) = iter.next() {
+            if !separated {
+                return Err(iter.error(&token, "',' before another node name"));
+            }
+            let name = token.text.strip_suffix(',');
+            separated = name.is_some();
+            let name = name.unwrap_or(token.text);
+            if name.is_empty() || name.contains(',') {
+                return Err(iter.error(&token, "a node name"));
+            }
+            neighbours.push(name);
+        }
+        if separated && !neighbours.is_empty() {
+            return Err(ParseError::at_end(s, "another node name"));
+        }
+        Ok((node, neighbours))
+    }
+
+    pub fn parse(input: &str) -> Result<Graph, ParseError> {
+        let mut graph = Graph::new();
+        for (node, neighbours) in parse::lines(input, parse_line)? {
+            let node = graph.add_node(node);
+            for neighbour in neighbours {
+                let neighbour = graph.add_node(neighbour);
+                graph.add_edge(node, neighbour);
+            }
+        }
+        Ok(graph)
+    }
+}
+
+pub mod export {
+    use std::fmt::Write;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum Value {
+        Int(i64),
+        Text(String),
+    }
+
+    impl Value {
+        fn graphml_type(&self) -> &'static str {
+            match self {
+                Value::Int(_) => "long",
+                Value::Text(_) => "string",
+            }
+        }
+
+        fn text(&self) -> String {
+            match self {
+                Value::Int(i) => i.to_string(),
+                Value::Text(s) => s.clone(),
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Node {
+        pub id: String,
+        pub label: String,
+        pub data: Vec<(String, Value)>,
+        pub fill: Option<String>,
+        pub highlight: bool,
+    }
+
+    impl Node {
+        pub fn new(id: &str) -> Node {
+            Node {
+                id: id.to_string(),
+                label: id.to_string(),
+                data: vec![],
+                fill: None,
+                highlight: false,
+            }
+        }
+    }
+
+    // A graph ready to be written out, with its edges given as indices
+    // into `nodes`.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Document {
+        pub name: String,
+        pub directed: bool,
+        pub nodes: Vec<Node>,
+        pub edges: Vec<(usize, usize)>,
+    }
+
+    // Distinguishable fill colours, which repeat if there are more than ten things to colour.
+    pub const PALETTE: [&str; 10] = [
+        "#8dd3c7", "#ffffb3", "#bebada", "#fb8072", "#80b1d3", "#fdb462", "#b3de69", "#fccde5",
+        "#d9d9d9", "#bc80bd",
+    ];
+
+    pub const HIGHLIGHT: &str = "red";
+
+    fn dot_quote(s: &str) -> String {
+        let mut quoted = String::with_capacity(s.len() + 2);
+        quoted.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    fn xml_escape(s: &str) -> String {
+        let mut escaped = String::with_capacity(s.len());
+        for c in s.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    impl Document {
+        // Every data key used by any node, in order of first use, with the
+        // type of its first value.
+        fn keys(&self) -> Vec<(&str, &'static str)> {
+            let mut keys: Vec<(&str, &'static str)> = vec![];
+            for node in &self.nodes {
+                for (key, value) in &node.data {
+                    if keys.iter().all(|(k, _)| k != key) {
+                        keys.push((key, value.graphml_type()));
+                    }
+                }
+            }
+            keys
+        }
+
+        pub fn to_dot(&self) -> String {
+            let (kind, arrow) = if self.directed {
+                ("digraph", "->")
+            } else {
+                ("graph", "--")
+            };
+            let mut out = String::new();
+            writeln!(out, "{} {} {{", kind, dot_quote(&self.name)).unwrap();
+            for node in &self.nodes {
+                let mut attributes = vec![format!("label={}", dot_quote(&node.label))];
+                let mut styles = vec![];
+                if let Some(fill) = &node.fill {
+                    styles.push("filled");
+                    attributes.push(format!("fillcolor={}", dot_quote(fill)));
+                }
+                if node.highlight {
+                    styles.push("bold");
+                    attributes.push(format!("color={}", dot_quote(HIGHLIGHT)));
+                    attributes.push("penwidth=3".to_string());
+                }
+                if !styles.is_empty() {
+                    attributes.push(format!("style={}", dot_quote(&styles.join(","))));
+                }
+                writeln!(
+                    out,
+                    "    {} [{}];",
+                    dot_quote(&node.id),
+                    attributes.join(", ")
+                )
+                .unwrap();
+            }
+            for &(a, b) in &self.edges {
+                writeln!(
+                    out,
+                    "    {} {} {};",
+                    dot_quote(&self.nodes[a].id),
+                    arrow,
+                    dot_quote(&self.nodes[b].id)
+                )
+                .unwrap();
+            }
+            out.push_str("}\n");
+            out
+        }
+
+        pub fn to_graphml(&self) -> String {
+            let mut out = String::new();
+            out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+            out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+            let mut keys = vec![("label", "string")];
+            keys.extend(self.keys());
+            keys.push(("fill", "string"));
+            keys.push(("highlight", "boolean"));
+            for (key, kind) in &keys {
+                writeln!(
+                    out,
+                    "  <key id=\"{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"{1}\"/>",
+                    xml_escape(key),
+                    kind
+                )
+                .unwrap();
+            }
+            let direction = if self.directed {
+                "directed"
+            } else {
+                "undirected"
+            };
+            writeln!(
+                out,
+                "  <graph id=\"{}\" edgedefault=\"{}\">",
+                xml_escape(&self.name),
+                direction
+            )
+            .unwrap();
+            for node in &self.nodes {
+                writeln!(out, "    <node id=\"{}\">", xml_escape(&node.id)).unwrap();
+                let mut data = vec![("label", node.label.clone())];
+                data.extend(node.data.iter().map(|(k, v)| (k.as_str(), v.text())));
+                if let Some(fill) = &node.fill {
+                    data.push(("fill", fill.clone()));
+                }
+                if node.highlight {
+                    data.push(("highlight", "true".to_string()));
+                }
+                for (key, value) in data {
+                    writeln!(
+                        out,
+                        "      <data key=\"{}\">{}</data>",
+                        xml_escape(key),
+                        xml_escape(&value)
+                    )
+                    .unwrap();
+                }
+                out.push_str("    </node>\n");
+            }
+            for &(a, b) in &self.edges {
+                writeln!(
+                    out,
+                    "    <edge source=\"{}\" target=\"{}\"/>",
+                    xml_escape(&self.nodes[a].id),
+                    xml_escape(&self.nodes[b].id)
+                )
+                .unwrap();
+            }
+            out.push_str("  </graph>\n</graphml>\n");
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::export::{self, Document, Value};
+    use super::graph::*;
+    use super::union_find::UnionFind;
+
+    fn example() -> Graph {
+        parse("0 <-> 2\n1 <-> 1\n2 <-> 0, 3, 4\n3 <-> 2, 4\n4 <-> 2, 3, 6\n5 <-> 6\n6 <-> 4, 5\n")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_union_find() {
+        let mut sets = UnionFind::new(5);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(3, 4));
+        assert!(!sets.union(1, 0));
+        assert!(sets.same(0, 1) && !sets.same(1, 3));
+        assert_eq!(sets.size_of(4), 2);
+        assert_eq!(sets.sets(), 3);
+    }
+
+    #[test]
+    fn test_parse() {
+        let graph = example();
+        assert_eq!(graph.len(), 7);
+        assert_eq!(graph.names(), ["0", "2", "1", "3", "4", "6", "5"]);
+        let node = |name| graph.index(name).unwrap();
+        let mut neighbours: Vec<&str> = graph
+            .neighbours(node("4"))
+            .iter()
+            .map(|&n| graph.name(n))
+            .collect();
+        neighbours.sort_unstable();
+        assert_eq!(neighbours, ["2", "3", "6"]);
+        assert_eq!(graph.edge_count(), 7);
+
+        let services = parse("web <-> db, cache\ncache <->\n").unwrap();
+        assert_eq!(services.neighbours(services.index("cache").unwrap()), [0]);
+
+        let error = parse("0 <-> 2\n1 -> 3").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+        assert_eq!(parse("0 <-> 2 , 3").unwrap_err().column, 9);
+        for line in &["0 <-> 1,2", "0 <-> 1,,", "0 <-> 1, ,2", "0 <-> ,"] {
+            assert_eq!(parse(line).unwrap_err().expected, "a node name");
+        }
+        let error = parse("0 <-> 1 2").unwrap_err();
+        assert_eq!((error.column, error.token.as_deref()), (9, Some("2")));
+        assert_eq!(
+            parse("0 <-> 1, 2,").unwrap_err().expected,
+            "another node name"
+        );
+    }
+
+    #[test]
+    fn test_components() {
+        let graph = example();
+        let node = |name| graph.index(name).unwrap();
+        let components = graph.components();
+        assert_eq!(components.count(), 2);
+        assert_eq!(components.sizes(), [6, 1]);
+        assert_eq!(components.size_of(node("0")), 6);
+        assert_eq!(
+            components.component_of(node("5")),
+            components.component_of(node("0"))
+        );
+        assert_eq!(components.members(1).collect::<Vec<_>>(), [node("1")]);
+    }
+
+    #[test]
+    fn test_paths() {
+        let graph = example();
+        let node = |name| graph.index(name).unwrap();
+        let distances = graph.distances(node("0"));
+        assert_eq!(distances[node("5")], Some(4));
+        assert_eq!(distances[node("1")], None);
+        let path: Vec<&str> = graph
+            .shortest_path(node("0"), node("5"))
+            .unwrap()
+            .into_iter()
+            .map(|n| graph.name(n))
+            .collect();
+        assert_eq!(path, ["0", "2", "4", "6", "5"]);
+        assert_eq!(
+            graph.shortest_path(node("1"), node("1")),
+            Some(vec![node("1")])
+        );
+        assert_eq!(graph.shortest_path(node("0"), node("1")), None);
+    }
+
+    #[test]
+    fn test_degrees() {
+        let degrees = example().degrees();
+        assert_eq!(degrees.histogram, [0, 3, 2, 2]);
+        assert_eq!((degrees.min(), degrees.max()), (Some(1), Some(3)));
+        assert_eq!(degrees.mean(), Some(13.0 / 7.0));
+        assert_eq!(Graph::new().degrees().mean(), None);
+    }
+
+    #[test]
+    fn test_to_document() {
+        let graph = parse("a <-> b\nc <-> c\n").unwrap();
+        let document = graph.to_document("pipes");
+        assert_eq!(
+            document.to_dot(),
+            r##"graph "pipes" {
+    "a" [label="a", fillcolor="#8dd3c7", style="filled"];
+    "b" [label="b", fillcolor="#8dd3c7", style="filled"];
+    "c" [label="c", fillcolor="#ffffb3", style="filled"];
+    "a" -- "b";
+    "c" -- "c";
+}
+"##
+        );
+        let graphml = document.to_graphml();
+        assert!(graphml.contains(
+            r#"<key id="component" for="node" attr.name="component" attr.type="long"/>"#
+        ));
+        assert!(graphml.contains(
+            "    <node id=\"c\">\n      <data key=\"label\">c</data>\n      <data key=\"component\">1</data>\n"
+        ));
+        assert!(graphml.contains(r#"<graph id="pipes" edgedefault="undirected">"#));
+        assert!(graphml.contains(r#"<edge source="a" target="b"/>"#));
+    }
+
+    #[test]
+    fn test_export_escaping() {
+        let mut node = export::Node::new("say \"hi\" & <bye>");
+        node.label = "two\nlines".to_string();
+        node.highlight = true;
+        node.data
+            .push(("note".to_string(), Value::Text("a<b".to_string())));
+        let document = Document {
+            name: "odd".to_string(),
+            directed: true,
+            nodes: vec![node],
+            edges: vec![(0, 0)],
+        };
+        assert_eq!(
+            document.to_dot(),
+            r#"digraph "odd" {
+    "say \"hi\" & <bye>" [label="two\nlines", color="red", penwidth=3, style="bold"];
+    "say \"hi\" & <bye>" -> "say \"hi\" & <bye>";
+}
+"#
+        );
+        let graphml = document.to_graphml();
+        assert!(graphml.contains(r#"<node id="say &quot;hi&quot; &amp; &lt;bye&gt;">"#));
+        assert!(graphml.contains(r#"<data key="note">a&lt;b</data>"#));
+        assert!(graphml.contains(r#"<data key="highlight">true</data>"#));
+        assert!(graphml.contains(r#"edgedefault="directed""#));
+    }
+}
@@ -0,0 +1,417 @@
+pub mod knothash {
+    use day_10::knot;
+    use day_14::day_14::KnotGrid;
+    use std::fs::File;
+    use std::io::{self, Read, Write};
+
+    pub const USAGE: &str =
+        "Usage: knothash [--format <hex|grid|raw>] [--string <TEXT>]... [FILE]...
+       knothash --check <MANIFEST>
+With no FILE or TEXT, or when FILE is -, read standard input.";
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Format {
+        Line(LineFormat),
+        // The sixteen bytes of each hash, back to back.
+        Raw,
+    }
+
+    // The formats which print a line per hash.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum LineFormat {
+        Hex,
+        // The hash as one row of day 14's grid: `#` for a 1 bit, `.` for a 0.
+        Grid,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Source {
+        Stdin,
+        File(String),
+        Text(String),
+    }
+
+    impl Source {
+        pub fn name(&self) -> String {
+            match self {
+                Source::Stdin => "-".to_string(),
+                Source::File(path) => path.clone(),
+                Source::Text(text) => format!("{:?}", text),
+            }
+        }
+
+        pub fn read(&self) -> io::Result<Vec<u8>> {
+            let mut bytes = vec![];
+            match self {
+                Source::Stdin => {
+                    io::stdin().read_to_end(&mut bytes)?;
+                }
+                Source::File(path) => {
+                    File::open(path)?.read_to_end(&mut bytes)?;
+                }
+                Source::Text(text) => bytes.extend_from_slice(text.as_bytes()),
+            }
+            Ok(bytes)
+        }
+    }
+
+    fn source_of_path(path: &str) -> Source {
+        if path == "-" {
+            Source::Stdin
+        } else {
+            Source::File(path.to_string())
+        }
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum Command {
+        Hash {
+            format: Format,
+            sources: Vec<Source>,
+        },
+        Check {
+            manifest: Source,
+        },
+    }
+
+    pub fn parse_args<I, S>(args: I) -> Result<Command, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let args: Vec<S> = args.into_iter().collect();
+        let mut args = args.iter().map(|s| s.as_ref());
+
+        let mut format = None;
+        let mut manifest = None;
+        let mut sources = vec![];
+        while let Some(arg) = args.next() {
+            match arg {
+                "--format" => {
+                    format = Some(match args.next() {
+                        Some("hex") => Format::Line(LineFormat::Hex),
+                        Some("grid") => Format::Line(LineFormat::Grid),
+                        Some("raw") => Format::Raw,
+                        Some(s) => {
+                            return Err(format!(
+                                "Expected hex, grid or raw after --format, got: {}",
+                                s
+                            ))
+                        }
+                        None => return Err("Expected a value after --format".to_string()),
+                    })
+                }
+                "--string" => match args.next() {
+                    Some(text) => sources.push(Source::Text(text.to_string())),
+                    None => return Err("Expected some text after --string".to_string()),
+                },
+                "--check" => match args.next() {
+                    Some(path) => manifest = Some(source_of_path(path)),
+                    None => return Err("Expected a manifest file after --check".to_string()),
+                },
+                "--" => sources.extend(args.by_ref().map(source_of_path)),
+                s if s.starts_with("--") => return Err(format!("Unrecognised argument: {}", s)),
+                path => sources.push(source_of_path(path)),
+            }
+        }
+
+        match manifest {
+            Some(manifest) => {
+                if format.is_some() || !sources.is_empty() {
+                    return Err("--check takes only a manifest file".to_string());
+                }
+                Ok(Command::Check { manifest })
+            }
+            None => {
+                if sources.is_empty() {
+                    sources.push(Source::Stdin);
+                }
+                Ok(Command::Hash {
+                    format: format.unwrap_or(Format::Line(LineFormat::Hex)),
+                    sources,
+                })
+            }
+        }
+    }
+
+    // One line of output, in the same layout as `sha256sum`, so that the
+    // output of the hex format can be fed straight back in to `--check`.
+    pub fn render_line(format: LineFormat, digest: &[u8; 16], name: &str) -> String {
+        let digest = match format {
+            LineFormat::Hex => day_10::day_10::to_hex_str(digest),
+            LineFormat::Grid => KnotGrid::from_rows(vec![u128::from_be_bytes(*digest)]).to_string(),
+        };
+        format!("{}  {}", digest, name)
+    }
+
+    pub fn write_digest<W>(
+        out: &mut W,
+        format: Format,
+        digest: &[u8; 16],
+        name: &str,
+    ) -> io::Result<()>
+    where
+        W: Write,
+    {
+        match format {
+            Format::Line(format) => writeln!(out, "{}", render_line(format, digest, name)),
+            Format::Raw => out.write_all(digest),
+        }
+    }
+
+    pub fn parse_hex(s: &str) -> Option<[u8; 16]> {
+        if s.len() != 32 || !s.is_ascii() {
+            return None;
+        }
+        let mut digest = [0u8; 16];
+        for (i, byte) in digest.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).ok()?;
+        }
+        Some(digest)
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct Entry {
+        pub digest: [u8; 16],
+        pub path: String,
+    }
+
+    // A manifest line is a hex digest, whitespace, and a path. As with
+    // `sha256sum`, the path may be marked with a leading `*`, which we ignore:
+    // knot hash has no separate text and binary modes.
+    pub fn parse_entry(line: &str) -> Option<Entry> {
+        let line = line.trim_end_matches('\r');
+        let split = line.find(char::is_whitespace)?;
+        let digest = parse_hex(&line[..split])?;
+        let path = line[split..].trim_start();
+        let path = path.strip_prefix('*').unwrap_or(path);
+        if path.is_empty() {
+            return None;
+        }
+        Some(Entry {
+            digest,
+            path: path.to_string(),
+        })
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Status {
+        Ok,
+        Failed,
+        Unreadable,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct Report {
+        pub results: Vec<(String, Status)>,
+        pub malformed: usize,
+    }
+
+    impl Report {
+        pub fn count(&self, status: Status) -> usize {
+            self.results.iter().filter(|(_, s)| *s == status).count()
+        }
+
+        pub fn passed(&self) -> bool {
+            !self.results.is_empty() && self.results.iter().all(|(_, s)| *s == Status::Ok)
+        }
+    }
+
+    pub fn check<F>(manifest: &str, mut read: F) -> Report
+    where
+        F: FnMut(&str) -> io::Result<Vec<u8>>,
+    {
+        let mut report = Report {
+            results: vec![],
+            malformed: 0,
+        };
+        for line in manifest.lines().filter(|l| !l.trim().is_empty()) {
+            let entry = match parse_entry(line) {
+                Some(entry) => entry,
+                None => {
+                    report.malformed += 1;
+                    continue;
+                }
+            };
+            let status = match read(&entry.path) {
+                Err(_) => Status::Unreadable,
+                Ok(bytes) if knot::hash(&bytes) == entry.digest => Status::Ok,
+                Ok(_) => Status::Failed,
+            };
+            report.results.push((entry.path, status));
+        }
+        report
+    }
+
+    pub fn render_status(path: &str, status: Status) -> String {
+        match status {
+            Status::Ok => format!("{}: OK", path),
+            Status::Failed => format!("{}: FAILED", path),
+            Status::Unreadable => format!("{}: FAILED open or read", path),
+        }
+    }
+
+    fn plural(n: usize, one: &str, many: &str) -> String {
+        format!("{} {}", n, if n == 1 { one } else { many })
+    }
+
+    pub fn render_warnings(report: &Report) -> Vec<String> {
+        let mut warnings = vec![];
+        if report.malformed > 0 {
+            warnings.push(format!(
+                "WARNING: {} improperly formatted",
+                plural(report.malformed, "line is", "lines are")
+            ));
+        }
+        let unreadable = report.count(Status::Unreadable);
+        if unreadable > 0 {
+            warnings.push(format!(
+                "WARNING: {} could not be read",
+                plural(unreadable, "listed file", "listed files")
+            ));
+        }
+        let failed = report.count(Status::Failed);
+        if failed > 0 {
+            warnings.push(format!(
+                "WARNING: {} did NOT match",
+                plural(failed, "computed checksum", "computed checksums")
+            ));
+        }
+        if report.results.is_empty() {
+            warnings.push("no properly formatted checksum lines found".to_string());
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::knothash::*;
+    use day_10::knot;
+    use std::io;
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(Vec::<&str>::new()),
+            Ok(Command::Hash {
+                format: Format::Line(LineFormat::Hex),
+                sources: vec![Source::Stdin],
+            })
+        );
+        assert_eq!(
+            parse_args(["--format", "grid", "a.txt", "--string", "AoC 2017", "-"]),
+            Ok(Command::Hash {
+                format: Format::Line(LineFormat::Grid),
+                sources: vec![
+                    Source::File("a.txt".to_string()),
+                    Source::Text("AoC 2017".to_string()),
+                    Source::Stdin,
+                ],
+            })
+        );
+        assert_eq!(
+            parse_args(["--", "--format"]),
+            Ok(Command::Hash {
+                format: Format::Line(LineFormat::Hex),
+                sources: vec![Source::File("--format".to_string())],
+            })
+        );
+        assert_eq!(
+            parse_args(["--check", "sums.txt"]),
+            Ok(Command::Check {
+                manifest: Source::File("sums.txt".to_string()),
+            })
+        );
+        assert!(parse_args(["--format", "base64"]).is_err());
+        assert!(parse_args(["--check", "sums.txt", "a.txt"]).is_err());
+        assert!(parse_args(["--string"]).is_err());
+        assert!(parse_args(["--verbose"]).is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let digest = knot::hash(b"AoC 2017");
+        assert_eq!(
+            render_line(LineFormat::Hex, &digest, "\"AoC 2017\""),
+            "33efeb34ea91902bb2f59c9920caa6cd  \"AoC 2017\""
+        );
+        let row = render_line(LineFormat::Grid, &digest, "-");
+        assert_eq!(row.len(), 131);
+        assert!(row.starts_with("..##..###"));
+        assert!(row.ends_with("##..##.#  -"));
+        assert_eq!(Source::Text("a b".to_string()).name(), "\"a b\"");
+
+        let mut out = vec![];
+        write_digest(&mut out, Format::Raw, &digest, "-").unwrap();
+        write_digest(&mut out, Format::Line(LineFormat::Hex), &digest, "-").unwrap();
+        assert_eq!(&out[..16], &digest);
+        assert_eq!(&out[16..], b"33efeb34ea91902bb2f59c9920caa6cd  -\n");
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        let hex = "33efeb34ea91902bb2f59c9920caa6cd";
+        let entry = parse_entry(&format!("{}  fixtures/a b.txt", hex)).unwrap();
+        assert_eq!(entry.digest, knot::hash(b"AoC 2017"));
+        assert_eq!(entry.path, "fixtures/a b.txt");
+        assert_eq!(
+            parse_entry(&format!("{} *a.bin", hex.to_uppercase()))
+                .unwrap()
+                .path,
+            "a.bin"
+        );
+        assert_eq!(parse_entry(hex), None);
+        assert_eq!(parse_entry(&format!("{}  ", hex)), None);
+        assert_eq!(parse_entry("33efeb34  a.txt"), None);
+        assert_eq!(
+            parse_entry(&format!("{}  a.txt", hex.replace('3', "g"))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let manifest = "\
+33efeb34ea91902bb2f59c9920caa6cd  good.txt
+33efeb34ea91902bb2f59c9920caa6cd  bad.txt
+not a checksum line
+
+a2582a3a0e66e6e86e3812dcb672a272  missing.txt
+";
+        let report = check(manifest, |path| match path {
+            "good.txt" => Ok(b"AoC 2017".to_vec()),
+            "bad.txt" => Ok(b"AoC 2018".to_vec()),
+            _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+        });
+        assert_eq!(
+            report.results,
+            vec![
+                ("good.txt".to_string(), Status::Ok),
+                ("bad.txt".to_string(), Status::Failed),
+                ("missing.txt".to_string(), Status::Unreadable),
+            ]
+        );
+        assert_eq!(report.malformed, 1);
+        assert!(!report.passed());
+        assert_eq!(
+            render_warnings(&report),
+            vec![
+                "WARNING: 1 line is improperly formatted",
+                "WARNING: 1 listed file could not be read",
+                "WARNING: 1 computed checksum did NOT match",
+            ]
+        );
+        assert_eq!(
+            render_status("missing.txt", Status::Unreadable),
+            "missing.txt: FAILED open or read"
+        );
+
+        let report = check("", |_| unreachable!());
+        assert!(!report.passed());
+        assert_eq!(
+            render_warnings(&report),
+            vec!["no properly formatted checksum lines found"]
+        );
+    }
+}
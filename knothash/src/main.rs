@@ -0,0 +1,74 @@
+use day_10::knot;
+use knothash::knothash;
+use std::io::{self, Write};
+use std::process;
+
+// A reader that has seen enough, such as `head`, closes the pipe; stop
+// quietly then, and complain about anything else.
+fn written(result: io::Result<()>) {
+    match result {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+        Err(e) => {
+            eprintln!("knothash: stdout: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let command = match knothash::parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("knothash: {}\n{}", e, knothash::USAGE);
+            process::exit(2);
+        }
+    };
+    let ok = match command {
+        knothash::Command::Hash { format, sources } => {
+            let stdout = io::stdout();
+            let mut out = stdout.lock();
+            let mut ok = true;
+            for source in sources {
+                match source.read() {
+                    Ok(bytes) => {
+                        let digest = knot::hash(&bytes);
+                        written(knothash::write_digest(
+                            &mut out,
+                            format,
+                            &digest,
+                            &source.name(),
+                        ));
+                    }
+                    Err(e) => {
+                        eprintln!("knothash: {}: {}", source.name(), e);
+                        ok = false;
+                    }
+                }
+            }
+            written(out.flush());
+            ok
+        }
+        knothash::Command::Check { manifest } => {
+            let bytes = manifest.read().unwrap_or_else(|e| {
+                eprintln!("knothash: {}: {}", manifest.name(), e);
+                process::exit(1);
+            });
+            let report =
+                knothash::check(&String::from_utf8_lossy(&bytes), |path| std::fs::read(path));
+            let stdout = io::stdout();
+            let mut out = stdout.lock();
+            for (path, status) in &report.results {
+                written(writeln!(out, "{}", knothash::render_status(path, *status)));
+            }
+            written(out.flush());
+            for warning in knothash::render_warnings(&report) {
+                eprintln!("knothash: {}: {}", manifest.name(), warning);
+            }
+            report.passed()
+        }
+    };
+    if !ok {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,25 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use vm::compiled::Backend;
+use vm::isa::{parse_program, Dialect};
+use vm::machine::{Effect, Machine};
+
+// About 400,000 steps of arithmetic and branching.
+const HOT_LOOP: &str = "set i 100000\nset a 1\nmul a 3\nmod a 1000003\nadd i -1\njgz i -3\nsnd a";
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let program = parse_program(Dialect::Duet, HOT_LOOP).unwrap();
+    for &(name, backend) in &[
+        ("interpreter", Backend::Interpreter),
+        ("compiled", Backend::Compiled),
+    ] {
+        c.bench_function(&format!("hot loop {}", name), |b| {
+            b.iter(|| {
+                let mut machine = Machine::with_backend(Dialect::Duet, &program, backend);
+                assert!(matches!(machine.run(), Effect::Send(_)));
+            })
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,1869 @@
+pub mod isa {
+    use common::parse::{self, ParseError, Tokens};
+    use std::fmt;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Register(u8);
+
+    impl Register {
+        pub fn new(name: char) -> Option<Register> {
+            if name.is_ascii_lowercase() {
+                Some(Register(name as u8 - b'a'))
+            } else {
+                None
+            }
+        }
+
+        pub fn index(self) -> usize {
+            self.0 as usize
+        }
+
+        pub fn name(self) -> char {
+            (b'a' + self.0) as char
+        }
+    }
+
+    impl fmt::Display for Register {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.name())
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Operand {
+        Register(Register),
+        Immediate(i64),
+    }
+
+    impl fmt::Display for Operand {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Operand::Register(r) => write!(f, "{}", r),
+                Operand::Immediate(i) => write!(f, "{}", i),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Instruction {
+        Set(Register, Operand),
+        Add(Register, Operand),
+        Sub(Register, Operand),
+        Mul(Register, Operand),
+        Mod(Register, Operand),
+        Snd(Operand),
+        Rcv(Register),
+        Jgz(Operand, Operand),
+        Jnz(Operand, Operand),
+    }
+
+    impl Instruction {
+        pub fn opcode(&self) -> &'static str {
+            match self {
+                Instruction::Set(_, _) => "set",
+                Instruction::Add(_, _) => "add",
+                Instruction::Sub(_, _) => "sub",
+                Instruction::Mul(_, _) => "mul",
+                Instruction::Mod(_, _) => "mod",
+                Instruction::Snd(_) => "snd",
+                Instruction::Rcv(_) => "rcv",
+                Instruction::Jgz(_, _) => "jgz",
+                Instruction::Jnz(_, _) => "jnz",
+            }
+        }
+    }
+
+    impl fmt::Display for Instruction {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{} ", self.opcode())?;
+            match self {
+                Instruction::Set(r, o)
+                | Instruction::Add(r, o)
+                | Instruction::Sub(r, o)
+                | Instruction::Mul(r, o)
+                | Instruction::Mod(r, o) => write!(f, "{} {}", r, o),
+                Instruction::Snd(o) => write!(f, "{}", o),
+                Instruction::Rcv(r) => write!(f, "{}", r),
+                Instruction::Jgz(c, o) | Instruction::Jnz(c, o) => write!(f, "{} {}", c, o),
+            }
+        }
+    }
+
+    // Which subset of the instruction set a puzzle uses.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Dialect {
+        // Day 18.
+        Duet,
+        // Day 23, which only has registers `a` to `h`.
+        Coprocessor,
+    }
+
+    impl Dialect {
+        pub fn registers(self) -> usize {
+            match self {
+                Dialect::Duet => 26,
+                Dialect::Coprocessor => 8,
+            }
+        }
+
+        pub fn opcodes(self) -> &'static [&'static str] {
+            match self {
+                Dialect::Duet => &["set", "add", "mul", "mod", "snd", "rcv", "jgz"],
+                Dialect::Coprocessor => &["set", "sub", "mul", "jnz"],
+            }
+        }
+
+        fn register_description(self) -> String {
+            let last = Register(self.registers() as u8 - 1);
+            format!("a register from 'a' to '{}'", last)
+        }
+    }
+
+    fn register(dialect: Dialect, tokens: &mut Tokens) -> Result<Register, ParseError> {
+        let expected = dialect.register_description();
+        let token = tokens.token(expected.as_str())?;
+        let mut chars = token.text.chars();
+        match (chars.next().and_then(Register::new), chars.next()) {
+            (Some(r), None) if r.index() < dialect.registers() => Ok(r),
+            _ => Err(tokens.error(&token, expected)),
+        }
+    }
+
+    fn operand(dialect: Dialect, tokens: &mut Tokens) -> Result<Operand, ParseError> {
+        let expected = format!("a number or {}", dialect.register_description());
+        let token = tokens.token(expected.as_str())?;
+        if let Ok(i) = token.text.parse() {
+            return Ok(Operand::Immediate(i));
+        }
+        let mut chars = token.text.chars();
+        match (chars.next().and_then(Register::new), chars.next()) {
+            (Some(r), None) if r.index() < dialect.registers() => Ok(Operand::Register(r)),
+            _ => Err(tokens.error(&token, expected)),
+        }
+    }
+
+    pub fn parse_instruction(dialect: Dialect, s: &str) -> Result<Instruction, ParseError> {
+        let mut tokens = Tokens::new(s);
+        let expected = format!("a {:?} instruction", dialect);
+        let token = tokens.token(expected.as_str())?;
+        if !dialect.opcodes().contains(&token.text) {
+            return Err(tokens.error(&token, expected));
+        }
+        let t = &mut tokens;
+        let instruction = match token.text {
+            "set" => Instruction::Set(register(dialect, t)?, operand(dialect, t)?),
+            "add" => Instruction::Add(register(dialect, t)?, operand(dialect, t)?),
+            "sub" => Instruction::Sub(register(dialect, t)?, operand(dialect, t)?),
+            "mul" => Instruction::Mul(register(dialect, t)?, operand(dialect, t)?),
+            "mod" => Instruction::Mod(register(dialect, t)?, operand(dialect, t)?),
+            "snd" => Instruction::Snd(operand(dialect, t)?),
+            "rcv" => Instruction::Rcv(register(dialect, t)?),
+            "jgz" => Instruction::Jgz(operand(dialect, t)?, operand(dialect, t)?),
+            "jnz" => Instruction::Jnz(operand(dialect, t)?, operand(dialect, t)?),
+            _ => return Err(tokens.error(&token, expected)),
+        };
+        tokens.end()?;
+        Ok(instruction)
+    }
+
+    pub fn parse_program(dialect: Dialect, input: &str) -> Result<Vec<Instruction>, ParseError> {
+        parse::lines(input, |l| parse_instruction(dialect, l))
+    }
+}
+
+pub mod channel {
+    use std::collections::VecDeque;
+
+    // A FIFO of messages to one machine, which remembers how much traffic it
+    // has seen.
+    #[derive(Clone)]
+    pub struct Channel<T> {
+        buffer: VecDeque<T>,
+        last_sent: Option<T>,
+        sent: usize,
+        received: usize,
+    }
+
+    impl<T> Channel<T>
+    where
+        T: Copy,
+    {
+        pub fn new() -> Channel<T> {
+            Channel {
+                buffer: VecDeque::new(),
+                last_sent: None,
+                sent: 0,
+                received: 0,
+            }
+        }
+
+        pub fn send(&mut self, t: T) {
+            self.buffer.push_back(t);
+            self.last_sent = Some(t);
+            self.sent += 1;
+        }
+
+        pub fn receive(&mut self) -> Option<T> {
+            let result = self.buffer.pop_front();
+            if result.is_some() {
+                self.received += 1;
+            }
+            result
+        }
+
+        // Puts back a message which was received, as if it never had been.
+        pub fn unreceive(&mut self, t: T) {
+            self.buffer.push_front(t);
+            self.received -= 1;
+        }
+
+        // Messages sent but not yet received.
+        pub fn pending(&self) -> usize {
+            self.buffer.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.buffer.is_empty()
+        }
+
+        pub fn last_sent(&self) -> Option<T> {
+            self.last_sent
+        }
+
+        pub fn sent(&self) -> usize {
+            self.sent
+        }
+
+        pub fn received(&self) -> usize {
+            self.received
+        }
+    }
+
+    impl<T> Default for Channel<T>
+    where
+        T: Copy,
+    {
+        fn default() -> Channel<T> {
+            Channel::new()
+        }
+    }
+}
+
+pub mod machine {
+    use crate::channel::Channel;
+    use crate::compiled::{self, Backend, Code};
+    use crate::isa::{Dialect, Instruction, Operand, Register};
+    use std::rc::Rc;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Effect {
+        Continue,
+        Send(i64),
+        Received(i64),
+        // Waiting on an empty inbox; the instruction will be retried.
+        Blocked,
+        Halted,
+    }
+
+    #[derive(Clone)]
+    pub struct Machine<'a> {
+        pub program: &'a [Instruction],
+        pub pc: usize,
+        pub registers: Vec<i64>,
+        pub inbox: Channel<i64>,
+        backend: Backend,
+        // Compiled on the first `run`, so that machines which are only ever
+        // stepped don't pay for it.
+        code: Option<Rc<Code>>,
+    }
+
+    impl<'a> Machine<'a> {
+        pub fn new(dialect: Dialect, program: &'a [Instruction]) -> Machine<'a> {
+            Machine::with_backend(dialect, program, Backend::default())
+        }
+
+        pub fn with_backend(
+            dialect: Dialect,
+            program: &'a [Instruction],
+            backend: Backend,
+        ) -> Machine<'a> {
+            Machine {
+                program,
+                pc: 0,
+                registers: vec![0; dialect.registers()],
+                inbox: Channel::new(),
+                backend,
+                code: None,
+            }
+        }
+
+        pub fn get(&self, r: Register) -> i64 {
+            self.registers[r.index()]
+        }
+
+        pub fn set(&mut self, r: Register, value: i64) {
+            self.registers[r.index()] = value;
+        }
+
+        pub fn value(&self, o: Operand) -> i64 {
+            match o {
+                Operand::Register(r) => self.get(r),
+                Operand::Immediate(i) => i,
+            }
+        }
+
+        // The instruction about to be executed, if we haven't halted.
+        pub fn current(&self) -> Option<&'a Instruction> {
+            self.program.get(self.pc)
+        }
+
+        fn jump(&mut self, offset: i64) -> Effect {
+            // A jump too far to even represent is certainly out of the program.
+            let target = (self.pc as i64).checked_add(offset).unwrap_or(-1);
+            if target < 0 || target >= self.program.len() as i64 {
+                self.pc = self.program.len();
+                Effect::Halted
+            } else {
+                self.pc = target as usize;
+                Effect::Continue
+            }
+        }
+
+        pub fn step(&mut self) -> Effect {
+            let instruction = match self.current() {
+                None => return Effect::Halted,
+                Some(&i) => i,
+            };
+            match instruction {
+                Instruction::Set(r, o) => self.set(r, self.value(o)),
+                Instruction::Add(r, o) => self.set(r, self.get(r) + self.value(o)),
+                Instruction::Sub(r, o) => self.set(r, self.get(r) - self.value(o)),
+                Instruction::Mul(r, o) => self.set(r, self.get(r) * self.value(o)),
+                Instruction::Mod(r, o) => self.set(r, self.get(r) % self.value(o)),
+                Instruction::Snd(o) => {
+                    self.pc += 1;
+                    return Effect::Send(self.value(o));
+                }
+                Instruction::Rcv(r) => {
+                    return match self.inbox.receive() {
+                        None => Effect::Blocked,
+                        Some(received) => {
+                            self.set(r, received);
+                            self.pc += 1;
+                            Effect::Received(received)
+                        }
+                    };
+                }
+                Instruction::Jgz(c, o) => {
+                    if self.value(c) > 0 {
+                        return self.jump(self.value(o));
+                    }
+                }
+                Instruction::Jnz(c, o) => {
+                    if self.value(c) != 0 {
+                        return self.jump(self.value(o));
+                    }
+                }
+            }
+            self.pc += 1;
+            Effect::Continue
+        }
+
+        // Runs until the machine sends, receives, blocks or halts.
+        pub fn run(&mut self) -> Effect {
+            if self.backend == Backend::Compiled {
+                let program = self.program;
+                let code = self
+                    .code
+                    .get_or_insert_with(|| Rc::new(compiled::compile(program)))
+                    .clone();
+                return code.run(self);
+            }
+            loop {
+                match self.step() {
+                    Effect::Continue => {}
+                    effect => return effect,
+                }
+            }
+        }
+    }
+}
+
+pub mod compiled {
+    use crate::isa::{Instruction, Operand};
+    use crate::machine::{Effect, Machine};
+    use std::str::FromStr;
+
+    // How `Machine::run` executes a program.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum Backend {
+        // Decodes each `Instruction` as it comes to it.
+        Interpreter,
+        // Translates the program up front into `Op`s.
+        #[default]
+        Compiled,
+    }
+
+    impl FromStr for Backend {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Backend, String> {
+            match s {
+                "interpreter" => Ok(Backend::Interpreter),
+                "compiled" => Ok(Backend::Compiled),
+                _ => Err(format!("Unknown backend '{}'", s)),
+            }
+        }
+    }
+
+    // An instruction with its registers resolved to indices, its operands
+    // split by kind, and its jump target resolved to an absolute PC (which is
+    // the length of the program if the jump halts).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Op {
+        Set(usize, usize),
+        SetI(usize, i64),
+        Add(usize, usize),
+        AddI(usize, i64),
+        Sub(usize, usize),
+        Mul(usize, usize),
+        MulI(usize, i64),
+        Mod(usize, usize),
+        ModI(usize, i64),
+        Snd(usize),
+        SndI(i64),
+        Rcv(usize),
+        Jump(usize),
+        Jgz(usize, usize),
+        Jnz(usize, usize),
+        // A jump whose target is only known at runtime.
+        Interpret,
+        Nop,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Code {
+        pub ops: Vec<Op>,
+    }
+
+    fn target(len: usize, pc: usize, offset: i64) -> usize {
+        let target = (pc as i64).checked_add(offset).unwrap_or(-1);
+        if target < 0 || target >= len as i64 {
+            len
+        } else {
+            target as usize
+        }
+    }
+
+    fn compile_one(len: usize, pc: usize, instruction: Instruction) -> Op {
+        use Operand::{Immediate, Register};
+        match instruction {
+            Instruction::Set(r, Register(s)) => Op::Set(r.index(), s.index()),
+            Instruction::Set(r, Immediate(i)) => Op::SetI(r.index(), i),
+            Instruction::Add(r, Register(s)) => Op::Add(r.index(), s.index()),
+            Instruction::Add(r, Immediate(i)) => Op::AddI(r.index(), i),
+            Instruction::Sub(r, Register(s)) => Op::Sub(r.index(), s.index()),
+            // `i64::MIN` has no negation to add, so leave it as it was.
+            Instruction::Sub(r, Immediate(i)) => match i.checked_neg() {
+                Some(i) => Op::AddI(r.index(), i),
+                None => Op::Interpret,
+            },
+            Instruction::Mul(r, Register(s)) => Op::Mul(r.index(), s.index()),
+            Instruction::Mul(r, Immediate(i)) => Op::MulI(r.index(), i),
+            Instruction::Mod(r, Register(s)) => Op::Mod(r.index(), s.index()),
+            Instruction::Mod(r, Immediate(i)) => Op::ModI(r.index(), i),
+            Instruction::Snd(Register(r)) => Op::Snd(r.index()),
+            Instruction::Snd(Immediate(i)) => Op::SndI(i),
+            Instruction::Rcv(r) => Op::Rcv(r.index()),
+            Instruction::Jgz(_, Register(_)) | Instruction::Jnz(_, Register(_)) => Op::Interpret,
+            Instruction::Jgz(Immediate(c), Immediate(o)) => {
+                if c > 0 {
+                    Op::Jump(target(len, pc, o))
+                } else {
+                    Op::Nop
+                }
+            }
+            Instruction::Jnz(Immediate(c), Immediate(o)) => {
+                if c != 0 {
+                    Op::Jump(target(len, pc, o))
+                } else {
+                    Op::Nop
+                }
+            }
+            Instruction::Jgz(Register(c), Immediate(o)) => Op::Jgz(c.index(), target(len, pc, o)),
+            Instruction::Jnz(Register(c), Immediate(o)) => Op::Jnz(c.index(), target(len, pc, o)),
+        }
+    }
+
+    pub fn compile(program: &[Instruction]) -> Code {
+        let len = program.len();
+        Code {
+            ops: program
+                .iter()
+                .enumerate()
+                .map(|(pc, &i)| compile_one(len, pc, i))
+                .collect(),
+        }
+    }
+
+    impl Code {
+        // Runs until the machine sends, receives, blocks or halts, just like
+        // `Machine::run`.
+        pub fn run(&self, machine: &mut Machine) -> Effect {
+            loop {
+                if let Some(effect) = self.run_native(machine) {
+                    return effect;
+                }
+                match machine.step() {
+                    Effect::Continue => {}
+                    effect => return effect,
+                }
+            }
+        }
+
+        // Returns `None` at an instruction which only the interpreter can do.
+        fn run_native(&self, machine: &mut Machine) -> Option<Effect> {
+            let ops = &self.ops[..];
+            let mut pc = machine.pc;
+            let registers = &mut machine.registers[..];
+            while let Some(&op) = ops.get(pc) {
+                pc += 1;
+                match op {
+                    Op::Set(r, s) => registers[r] = registers[s],
+                    Op::SetI(r, i) => registers[r] = i,
+                    Op::Add(r, s) => registers[r] += registers[s],
+                    Op::AddI(r, i) => registers[r] += i,
+                    Op::Sub(r, s) => registers[r] -= registers[s],
+                    Op::Mul(r, s) => registers[r] *= registers[s],
+                    Op::MulI(r, i) => registers[r] *= i,
+                    Op::Mod(r, s) => registers[r] %= registers[s],
+                    Op::ModI(r, i) => registers[r] %= i,
+                    Op::Snd(r) => {
+                        machine.pc = pc;
+                        return Some(Effect::Send(registers[r]));
+                    }
+                    Op::SndI(i) => {
+                        machine.pc = pc;
+                        return Some(Effect::Send(i));
+                    }
+                    Op::Rcv(r) => {
+                        return Some(match machine.inbox.receive() {
+                            None => {
+                                machine.pc = pc - 1;
+                                Effect::Blocked
+                            }
+                            Some(received) => {
+                                registers[r] = received;
+                                machine.pc = pc;
+                                Effect::Received(received)
+                            }
+                        });
+                    }
+                    Op::Jump(t) => pc = t,
+                    Op::Jgz(c, t) => {
+                        if registers[c] > 0 {
+                            pc = t;
+                        }
+                    }
+                    Op::Jnz(c, t) => {
+                        if registers[c] != 0 {
+                            pc = t;
+                        }
+                    }
+                    Op::Interpret => {
+                        machine.pc = pc - 1;
+                        return None;
+                    }
+                    Op::Nop => {}
+                }
+            }
+            machine.pc = ops.len();
+            Some(Effect::Halted)
+        }
+    }
+}
+
+pub mod scheduler {
+    use crate::machine::{Effect, Machine};
+
+    // Where each machine's sends are delivered. A message is copied to every
+    // machine in the list.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Topology {
+        pub routes: Vec<Vec<usize>>,
+    }
+
+    impl Topology {
+        // Machine i sends to machine i + 1, wrapping around. A ring of one
+        // machine talks to itself, and a ring of two is day 18's duet.
+        pub fn ring(n: usize) -> Topology {
+            Topology {
+                routes: (0..n).map(|i| vec![(i + 1) % n]).collect(),
+            }
+        }
+
+        // Machine 0 broadcasts to every other machine, and they all send to
+        // machine 0.
+        pub fn star(n: usize) -> Topology {
+            let mut routes = vec![(1..n).collect()];
+            routes.extend((1..n).map(|_| vec![0]));
+            Topology { routes }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Outcome {
+        // Every machine ran off the end of its program.
+        Halted,
+        // Every machine that hasn't halted is waiting on an empty inbox.
+        Deadlock,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub struct Stats {
+        pub sent: usize,
+        pub received: usize,
+        pub halted: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Report {
+        pub outcome: Outcome,
+        pub machines: Vec<Stats>,
+    }
+
+    pub struct Scheduler<'a> {
+        pub machines: Vec<Machine<'a>>,
+        topology: Topology,
+        stats: Vec<Stats>,
+    }
+
+    impl<'a> Scheduler<'a> {
+        pub fn new(
+            machines: Vec<Machine<'a>>,
+            topology: Topology,
+        ) -> Result<Scheduler<'a>, String> {
+            if machines.len() != topology.routes.len() {
+                return Err(format!(
+                    "Topology is for {} machines, but there are {}",
+                    topology.routes.len(),
+                    machines.len()
+                ));
+            }
+            for (from, targets) in topology.routes.iter().enumerate() {
+                if let Some(to) = targets.iter().find(|&&t| t >= machines.len()) {
+                    return Err(format!(
+                        "Topology routes machine {} to machine {}, which doesn't exist",
+                        from, to
+                    ));
+                }
+            }
+            let stats = vec![Stats::default(); machines.len()];
+            Ok(Scheduler {
+                machines,
+                topology,
+                stats,
+            })
+        }
+
+        fn deliver(&mut self, from: usize, value: i64) {
+            self.stats[from].sent += 1;
+            for &to in self.topology.routes[from].iter() {
+                self.machines[to].inbox.send(value);
+            }
+        }
+
+        // Runs machine i until it blocks or halts, and returns whether it did
+        // anything.
+        fn run_one(&mut self, i: usize) -> bool {
+            let mut progress = false;
+            loop {
+                match self.machines[i].run() {
+                    Effect::Send(value) => self.deliver(i, value),
+                    Effect::Received(_) => self.stats[i].received += 1,
+                    Effect::Blocked => return progress,
+                    Effect::Halted | Effect::Continue => {
+                        self.stats[i].halted = true;
+                        return true;
+                    }
+                }
+                progress = true;
+            }
+        }
+
+        // Takes turns running each machine until they have all halted or are
+        // all stuck.
+        pub fn run(&mut self) -> Report {
+            loop {
+                let mut progress = false;
+                for i in 0..self.machines.len() {
+                    if !self.stats[i].halted {
+                        progress |= self.run_one(i);
+                    }
+                }
+                if !progress {
+                    let outcome = if self.stats.iter().all(|s| s.halted) {
+                        Outcome::Halted
+                    } else {
+                        Outcome::Deadlock
+                    };
+                    return Report {
+                        outcome,
+                        machines: self.stats.clone(),
+                    };
+                }
+            }
+        }
+    }
+}
+
+pub mod trace {
+    use crate::isa::{Instruction, Register};
+    use crate::machine::{Effect, Machine};
+    use std::fmt;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ChannelEvent {
+        Sent(i64),
+        Received(i64),
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Delta {
+        pub register: Register,
+        pub old: i64,
+        pub new: i64,
+    }
+
+    // Everything needed to display, or to undo, one executed instruction.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Entry {
+        pub pc: usize,
+        pub instruction: Instruction,
+        pub deltas: Vec<Delta>,
+        pub event: Option<ChannelEvent>,
+    }
+
+    impl fmt::Display for Entry {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{:>4}: {}", self.pc, self.instruction)?;
+            for d in self.deltas.iter() {
+                write!(f, "  {}: {} -> {}", d.register, d.old, d.new)?;
+            }
+            match self.event {
+                None => Ok(()),
+                Some(ChannelEvent::Sent(v)) => write!(f, "  sent {}", v),
+                Some(ChannelEvent::Received(v)) => write!(f, "  received {}", v),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Comparison {
+        Equal,
+        NotEqual,
+        Less,
+        LessEqual,
+        Greater,
+        GreaterEqual,
+    }
+
+    impl Comparison {
+        pub fn parse(s: &str) -> Option<Comparison> {
+            match s {
+                "==" => Some(Comparison::Equal),
+                "!=" => Some(Comparison::NotEqual),
+                "<" => Some(Comparison::Less),
+                "<=" => Some(Comparison::LessEqual),
+                ">" => Some(Comparison::Greater),
+                ">=" => Some(Comparison::GreaterEqual),
+                _ => None,
+            }
+        }
+
+        pub fn holds(self, x: i64, y: i64) -> bool {
+            match self {
+                Comparison::Equal => x == y,
+                Comparison::NotEqual => x != y,
+                Comparison::Less => x < y,
+                Comparison::LessEqual => x <= y,
+                Comparison::Greater => x > y,
+                Comparison::GreaterEqual => x >= y,
+            }
+        }
+    }
+
+    impl fmt::Display for Comparison {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let s = match self {
+                Comparison::Equal => "==",
+                Comparison::NotEqual => "!=",
+                Comparison::Less => "<",
+                Comparison::LessEqual => "<=",
+                Comparison::Greater => ">",
+                Comparison::GreaterEqual => ">=",
+            };
+            write!(f, "{}", s)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Breakpoint {
+        // About to execute the instruction at this address.
+        Pc(usize),
+        Register(Register, Comparison, i64),
+    }
+
+    impl Breakpoint {
+        pub fn hit(&self, machine: &Machine) -> bool {
+            match *self {
+                Breakpoint::Pc(pc) => machine.pc == pc,
+                // A register the machine doesn't have can never match.
+                Breakpoint::Register(r, cmp, value) => machine
+                    .registers
+                    .get(r.index())
+                    .is_some_and(|&v| cmp.holds(v, value)),
+            }
+        }
+    }
+
+    impl fmt::Display for Breakpoint {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Breakpoint::Pc(pc) => write!(f, "pc {}", pc),
+                Breakpoint::Register(r, cmp, value) => write!(f, "{} {} {}", r, cmp, value),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Stop {
+        // The index of the breakpoint that fired.
+        Breakpoint(usize),
+        Blocked,
+        Halted,
+        Limit,
+    }
+
+    // Runs a single machine, recording every instruction so that it can be
+    // inspected or undone. Anything the machine sends ends up in `outbox`.
+    pub struct Debugger<'a> {
+        pub machine: Machine<'a>,
+        pub trace: Vec<Entry>,
+        pub outbox: Vec<i64>,
+        pub breakpoints: Vec<Breakpoint>,
+    }
+
+    impl<'a> Debugger<'a> {
+        pub fn new(machine: Machine<'a>) -> Debugger<'a> {
+            Debugger {
+                machine,
+                trace: vec![],
+                outbox: vec![],
+                breakpoints: vec![],
+            }
+        }
+
+        // Executes one instruction. Nothing is recorded if the machine has
+        // halted or is blocked, because nothing happened.
+        pub fn step(&mut self) -> Effect {
+            let (pc, instruction) = match self.machine.current() {
+                None => return Effect::Halted,
+                Some(&i) => (self.machine.pc, i),
+            };
+            let before = self.machine.registers.clone();
+            let effect = self.machine.step();
+            let event = match effect {
+                Effect::Blocked => return effect,
+                Effect::Send(v) => {
+                    self.outbox.push(v);
+                    Some(ChannelEvent::Sent(v))
+                }
+                Effect::Received(v) => Some(ChannelEvent::Received(v)),
+                Effect::Continue | Effect::Halted => None,
+            };
+            let deltas = before
+                .iter()
+                .zip(self.machine.registers.iter())
+                .enumerate()
+                .filter(|(_, (old, new))| old != new)
+                .map(|(i, (&old, &new))| Delta {
+                    register: Register::new((b'a' + i as u8) as char).unwrap(),
+                    old,
+                    new,
+                })
+                .collect();
+            self.trace.push(Entry {
+                pc,
+                instruction,
+                deltas,
+                event,
+            });
+            effect
+        }
+
+        // Undoes the most recent instruction, returning it.
+        pub fn step_back(&mut self) -> Option<Entry> {
+            let entry = self.trace.pop()?;
+            self.machine.pc = entry.pc;
+            for d in entry.deltas.iter() {
+                self.machine.set(d.register, d.old);
+            }
+            match entry.event {
+                None => {}
+                Some(ChannelEvent::Sent(_)) => {
+                    self.outbox.pop();
+                }
+                Some(ChannelEvent::Received(v)) => self.machine.inbox.unreceive(v),
+            }
+            Some(entry)
+        }
+
+        // Steps until a breakpoint fires, the machine stops, or `limit`
+        // instructions have run. Always executes at least one instruction, so
+        // that continuing from a breakpoint makes progress.
+        pub fn run(&mut self, limit: Option<u64>) -> Stop {
+            let mut steps = 0;
+            loop {
+                if limit.is_some_and(|l| steps >= l) {
+                    return Stop::Limit;
+                }
+                match self.step() {
+                    Effect::Blocked => return Stop::Blocked,
+                    Effect::Halted => return Stop::Halted,
+                    _ => {}
+                }
+                steps += 1;
+                if let Some(i) = self.breakpoints.iter().position(|b| b.hit(&self.machine)) {
+                    return Stop::Breakpoint(i);
+                }
+            }
+        }
+    }
+}
+
+pub mod optimise {
+    use crate::isa::{Instruction, Operand, Register};
+    use crate::machine::{Effect, Machine};
+    use std::fmt;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Successor {
+        Block(usize),
+        Halt,
+        // A jump by a register amount.
+        Unknown,
+    }
+
+    // Instructions `start..end`, of which only the last can jump.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Block {
+        pub start: usize,
+        pub end: usize,
+        pub successors: Vec<Successor>,
+    }
+
+    // A loop closed by a backwards jump at `latch` to `head`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Loop {
+        pub head: usize,
+        pub latch: usize,
+    }
+
+    // The jump target of instruction `pc`, and whether the jump is always
+    // taken; `None` if it isn't a jump, or is never taken.
+    fn jump(program: &[Instruction], pc: usize) -> Option<(Option<i64>, bool)> {
+        let (condition, offset, always) = match program[pc] {
+            Instruction::Jgz(c, o) => (c, o, matches!(c, Operand::Immediate(i) if i > 0)),
+            Instruction::Jnz(c, o) => (c, o, matches!(c, Operand::Immediate(i) if i != 0)),
+            _ => return None,
+        };
+        if let Operand::Immediate(_) = condition {
+            if !always {
+                return None;
+            }
+        }
+        match offset {
+            // Overflowing is just another way of leaving the program.
+            Operand::Immediate(o) => Some((Some((pc as i64).checked_add(o).unwrap_or(-1)), always)),
+            Operand::Register(_) => Some((None, always)),
+        }
+    }
+
+    pub struct Cfg {
+        pub blocks: Vec<Block>,
+    }
+
+    impl Cfg {
+        pub fn new(program: &[Instruction]) -> Cfg {
+            let len = program.len();
+            let mut leader = vec![false; len + 1];
+            leader[0] = true;
+            for pc in 0..len {
+                if let Some((target, _)) = jump(program, pc) {
+                    leader[pc + 1] = true;
+                    if let Some(t) = target {
+                        if t >= 0 && (t as usize) < len {
+                            leader[t as usize] = true;
+                        }
+                    }
+                }
+            }
+            let starts: Vec<usize> = (0..len).filter(|&pc| leader[pc]).collect();
+            let block_at = |pc: i64| -> Successor {
+                if pc < 0 || pc >= len as i64 {
+                    Successor::Halt
+                } else {
+                    Successor::Block(starts.binary_search(&(pc as usize)).unwrap())
+                }
+            };
+            let blocks = starts
+                .iter()
+                .enumerate()
+                .map(|(i, &start)| {
+                    let end = starts.get(i + 1).copied().unwrap_or(len);
+                    let last = end - 1;
+                    let mut successors = vec![];
+                    match jump(program, last) {
+                        None => successors.push(block_at(end as i64)),
+                        Some((target, always)) => {
+                            successors.push(target.map_or(Successor::Unknown, block_at));
+                            if !always {
+                                successors.push(block_at(end as i64));
+                            }
+                        }
+                    }
+                    Block {
+                        start,
+                        end,
+                        successors,
+                    }
+                })
+                .collect();
+            Cfg { blocks }
+        }
+
+        // Every backwards jump, innermost loops first.
+        pub fn loops(&self) -> Vec<Loop> {
+            let mut loops: Vec<Loop> = self
+                .blocks
+                .iter()
+                .flat_map(|b| {
+                    b.successors.iter().filter_map(move |s| match *s {
+                        Successor::Block(t) if self.blocks[t].start <= b.start => Some(Loop {
+                            head: self.blocks[t].start,
+                            latch: b.end - 1,
+                        }),
+                        _ => None,
+                    })
+                })
+                .collect();
+            loops.sort_by_key(|l| l.latch - l.head);
+            loops
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Idiom {
+        // do { counter += 1 } while counter != bound
+        CountUp {
+            counter: Register,
+            bound: Operand,
+            scratch: Register,
+        },
+        // do {
+        //     inner = inner_start
+        //     do {
+        //         if outer * inner == target { flag = 0 }
+        //         inner += 1
+        //     } while inner != target
+        //     outer += 1
+        // } while outer != target
+        FactorSearch {
+            flag: Register,
+            target: Operand,
+            outer: Register,
+            inner: Register,
+            inner_start: i64,
+            scratch: Register,
+        },
+    }
+
+    fn has_factor_pair(target: i64, outer: i64, inner: i64) -> bool {
+        let in_range = |d: i64, e: i64| outer <= d && d < target && inner <= e && e < target;
+        let mut k = 1;
+        while k * k <= target {
+            if target % k == 0 && (in_range(k, target / k) || in_range(target / k, k)) {
+                return true;
+            }
+            k += 1;
+        }
+        false
+    }
+
+    impl Idiom {
+        // Does the whole loop in one go, returning false (and doing nothing)
+        // if the closed form doesn't apply to the machine's current state.
+        fn apply(&self, machine: &mut Machine) -> bool {
+            match *self {
+                Idiom::CountUp {
+                    counter,
+                    bound,
+                    scratch,
+                } => {
+                    let bound = machine.value(bound);
+                    if machine.get(counter) >= bound {
+                        return false;
+                    }
+                    machine.set(counter, bound);
+                    machine.set(scratch, 0);
+                }
+                Idiom::FactorSearch {
+                    flag,
+                    target,
+                    outer,
+                    inner,
+                    inner_start,
+                    scratch,
+                } => {
+                    let target = machine.value(target);
+                    let outer_start = machine.get(outer);
+                    if outer_start < 1 || inner_start < 1 {
+                        return false;
+                    }
+                    if outer_start >= target || inner_start >= target {
+                        return false;
+                    }
+                    if has_factor_pair(target, outer_start, inner_start) {
+                        machine.set(flag, 0);
+                    }
+                    machine.set(outer, target);
+                    machine.set(inner, target);
+                    machine.set(scratch, 0);
+                }
+            }
+            true
+        }
+    }
+
+    impl fmt::Display for Idiom {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Idiom::CountUp {
+                    counter,
+                    bound,
+                    scratch,
+                } => write!(f, "{} = {}; {} = 0", counter, bound, scratch),
+                Idiom::FactorSearch {
+                    flag,
+                    target,
+                    outer,
+                    inner,
+                    inner_start,
+                    scratch,
+                } => write!(
+                    f,
+                    "if {t} = x * y for some x in {o}..{t}, y in {s}..{t} {{ {fl} = 0 }}; {o} = {t}; {i} = {t}; {sc} = 0",
+                    t = target,
+                    o = outer,
+                    s = inner_start,
+                    i = inner,
+                    fl = flag,
+                    sc = scratch
+                ),
+            }
+        }
+    }
+
+    fn distinct(registers: &[Register]) -> bool {
+        registers
+            .iter()
+            .enumerate()
+            .all(|(i, r)| !registers[..i].contains(r))
+    }
+
+    fn not_register(o: Operand, registers: &[Register]) -> bool {
+        match o {
+            Operand::Register(r) => !registers.contains(&r),
+            Operand::Immediate(_) => true,
+        }
+    }
+
+    // `sub counter -1; set scratch counter; sub scratch bound; jnz scratch -3`
+    fn count_up(code: &[Instruction]) -> Option<Idiom> {
+        use Instruction::*;
+        use Operand::*;
+        match *code {
+            [Sub(counter, Immediate(-1)), Set(scratch, Register(c)), Sub(s, bound), Jnz(Register(s2), Immediate(-3))]
+                if c == counter
+                    && s == scratch
+                    && s2 == scratch
+                    && distinct(&[counter, scratch])
+                    && not_register(bound, &[counter, scratch]) =>
+            {
+                Some(Idiom::CountUp {
+                    counter,
+                    bound,
+                    scratch,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // The shape of day 23's primality test.
+    fn factor_search(code: &[Instruction]) -> Option<Idiom> {
+        use Instruction::*;
+        use Operand::*;
+        match *code {
+            [Set(inner, Immediate(inner_start)), Set(scratch, Register(x)), Mul(s1, Register(y)), Sub(s2, target), Jnz(Register(s3), Immediate(2)), Set(flag, Immediate(0)), Sub(i1, Immediate(-1)), Set(s4, Register(i2)), Sub(s5, t1), Jnz(Register(s6), Immediate(-8)), Sub(outer, Immediate(-1)), Set(s7, Register(o1)), Sub(s8, t2), Jnz(Register(s9), Immediate(-13))]
+                if [s1, s2, s3, s4, s5, s6, s7, s8, s9]
+                    .iter()
+                    .all(|&s| s == scratch)
+                    && ((x, y) == (outer, inner) || (x, y) == (inner, outer))
+                    && [i1, i2] == [inner, inner]
+                    && o1 == outer
+                    && t1 == target
+                    && t2 == target
+                    && distinct(&[flag, outer, inner, scratch])
+                    && not_register(target, &[flag, outer, inner, scratch]) =>
+            {
+                Some(Idiom::FactorSearch {
+                    flag,
+                    target,
+                    outer,
+                    inner,
+                    inner_start,
+                    scratch,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // A recognised loop, which can be skipped by applying `idiom` at `head`
+    // and carrying on from `exit`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Shortcut {
+        pub head: usize,
+        pub exit: usize,
+        pub idiom: Idiom,
+    }
+
+    pub struct Optimised<'a> {
+        pub program: &'a [Instruction],
+        // Indexed by the PC of the loop head.
+        pub shortcuts: Vec<Option<Shortcut>>,
+    }
+
+    pub fn optimise(program: &[Instruction]) -> Optimised<'_> {
+        let mut shortcuts = vec![None; program.len()];
+        for l in Cfg::new(program).loops() {
+            let code = &program[l.head..=l.latch];
+            // Loops are innermost first, so an outer loop replaces any
+            // shortcut for an inner one with the same head.
+            if let Some(idiom) = count_up(code).or_else(|| factor_search(code)) {
+                shortcuts[l.head] = Some(Shortcut {
+                    head: l.head,
+                    exit: l.latch + 1,
+                    idiom,
+                });
+            }
+        }
+        Optimised { program, shortcuts }
+    }
+
+    impl Optimised<'_> {
+        // Like `Machine::step`, but does a whole recognised loop at once.
+        pub fn step(&self, machine: &mut Machine) -> Effect {
+            if let Some(Some(shortcut)) = self.shortcuts.get(machine.pc) {
+                if shortcut.idiom.apply(machine) {
+                    machine.pc = shortcut.exit;
+                    return if machine.pc >= self.program.len() {
+                        Effect::Halted
+                    } else {
+                        Effect::Continue
+                    };
+                }
+            }
+            machine.step()
+        }
+
+        // Runs until the machine sends, receives, blocks or halts.
+        pub fn run(&self, machine: &mut Machine) -> Effect {
+            loop {
+                match self.step(machine) {
+                    Effect::Continue => {}
+                    effect => return effect,
+                }
+            }
+        }
+    }
+
+    // The program, with each recognised loop replaced by its closed form.
+    impl fmt::Display for Optimised<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let mut pc = 0;
+            while pc < self.program.len() {
+                match self.shortcuts[pc] {
+                    Some(s) => {
+                        writeln!(f, "{:>4}: {}", pc, s.idiom)?;
+                        pc = s.exit;
+                    }
+                    None => {
+                        writeln!(f, "{:>4}: {}", pc, self.program[pc])?;
+                        pc += 1;
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+pub mod repl {
+    use crate::isa::{self, Register};
+    use crate::trace::{Breakpoint, Comparison, Debugger, Stop};
+    use std::fmt::Write;
+
+    pub const HELP: &str = "\
+step [N]          execute N instructions (default 1)
+back [N]          undo N instructions (default 1)
+continue [N]      run until a breakpoint, or at most N instructions
+break pc N        stop before executing instruction N
+break R OP N      stop when register R compares to N, e.g. `break h != 0`
+delete N          remove breakpoint N
+breakpoints       list breakpoints
+registers         show the registers
+set R N           set register R to N
+send N            put N in the machine's inbox
+trace [N]         show the last N executed instructions (default 10)
+list              show the program
+quit";
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Command {
+        Step(u64),
+        Back(u64),
+        Continue(Option<u64>),
+        Break(Breakpoint),
+        Delete(usize),
+        Breakpoints,
+        Registers,
+        Set(Register, i64),
+        Send(i64),
+        Trace(usize),
+        List,
+        Help,
+        Quit,
+    }
+
+    fn number<T>(s: Option<&str>, default: Option<T>) -> Result<T, String>
+    where
+        T: std::str::FromStr,
+    {
+        match (s, default) {
+            (None, Some(d)) => Ok(d),
+            (None, None) => Err("Expected a number".to_string()),
+            (Some(s), _) => s
+                .parse()
+                .map_err(|_| format!("Expected a number, got: {}", s)),
+        }
+    }
+
+    fn register(s: Option<&str>) -> Result<Register, String> {
+        let s = s.ok_or_else(|| "Expected a register".to_string())?;
+        let mut chars = s.chars();
+        match (chars.next().and_then(Register::new), chars.next()) {
+            (Some(r), None) => Ok(r),
+            _ => Err(format!("Expected a register, got: {}", s)),
+        }
+    }
+
+    pub fn parse_command(s: &str) -> Result<Command, String> {
+        let mut words = s.split_whitespace();
+        let command = match words.next() {
+            None => return Err("Expected a command; try `help`".to_string()),
+            Some("step") | Some("s") => Command::Step(number(words.next(), Some(1))?),
+            Some("back") | Some("b") => Command::Back(number(words.next(), Some(1))?),
+            Some("continue") | Some("c") => match words.next() {
+                None => Command::Continue(None),
+                limit => Command::Continue(Some(number(limit, None)?)),
+            },
+            Some("break") => match words.next() {
+                Some("pc") => Command::Break(Breakpoint::Pc(number(words.next(), None)?)),
+                r => {
+                    let r = register(r)?;
+                    let cmp = words.next().unwrap_or("");
+                    let cmp = Comparison::parse(cmp)
+                        .ok_or_else(|| format!("Expected a comparison, got: {}", cmp))?;
+                    Command::Break(Breakpoint::Register(r, cmp, number(words.next(), None)?))
+                }
+            },
+            Some("delete") => Command::Delete(number(words.next(), None)?),
+            Some("breakpoints") => Command::Breakpoints,
+            Some("registers") | Some("r") => Command::Registers,
+            Some("set") => Command::Set(register(words.next())?, number(words.next(), None)?),
+            Some("send") => Command::Send(number(words.next(), None)?),
+            Some("trace") | Some("t") => Command::Trace(number(words.next(), Some(10))?),
+            Some("list") | Some("l") => Command::List,
+            Some("help") | Some("h") => Command::Help,
+            Some("quit") | Some("q") => Command::Quit,
+            Some(c) => return Err(format!("Unrecognised command: {}; try `help`", c)),
+        };
+        match words.next() {
+            None => Ok(command),
+            Some(w) => Err(format!("Unexpected argument: {}", w)),
+        }
+    }
+
+    fn describe_position(debugger: &Debugger) -> String {
+        match debugger.machine.current() {
+            None => format!("halted after {} steps", debugger.trace.len()),
+            Some(i) => format!(
+                "step {}, next {:>4}: {}",
+                debugger.trace.len(),
+                debugger.machine.pc,
+                i
+            ),
+        }
+    }
+
+    fn describe_stop(debugger: &Debugger, stop: Stop) -> String {
+        let reason = match stop {
+            Stop::Breakpoint(i) => format!("breakpoint {} ({})", i, debugger.breakpoints[i]),
+            Stop::Blocked => "blocked on an empty inbox".to_string(),
+            Stop::Halted => "halted".to_string(),
+            Stop::Limit => "step limit".to_string(),
+        };
+        format!("Stopped: {}; {}", reason, describe_position(debugger))
+    }
+
+    // Carries out a command, returning what to print. `Quit` is the caller's
+    // responsibility.
+    pub fn execute(debugger: &mut Debugger, command: &Command) -> String {
+        let mut out = String::new();
+        match *command {
+            Command::Step(n) => {
+                let before = debugger.trace.len();
+                let stop = debugger.run(Some(n));
+                for entry in debugger.trace[before..].iter() {
+                    writeln!(out, "{}", entry).unwrap();
+                }
+                match stop {
+                    Stop::Limit => write!(out, "{}", describe_position(debugger)).unwrap(),
+                    stop => write!(out, "{}", describe_stop(debugger, stop)).unwrap(),
+                }
+            }
+            Command::Back(n) => {
+                for _ in 0..n {
+                    match debugger.step_back() {
+                        None => {
+                            writeln!(out, "At the start of the trace").unwrap();
+                            break;
+                        }
+                        Some(entry) => writeln!(out, "undid {}", entry).unwrap(),
+                    }
+                }
+                write!(out, "{}", describe_position(debugger)).unwrap();
+            }
+            Command::Continue(limit) => {
+                let stop = debugger.run(limit);
+                write!(out, "{}", describe_stop(debugger, stop)).unwrap();
+            }
+            Command::Break(Breakpoint::Register(r, _, _))
+                if r.index() >= debugger.machine.registers.len() =>
+            {
+                write!(out, "No register {} in this machine", r).unwrap();
+            }
+            Command::Break(b) => {
+                debugger.breakpoints.push(b);
+                write!(out, "Breakpoint {}: {}", debugger.breakpoints.len() - 1, b).unwrap();
+            }
+            Command::Delete(i) => {
+                if i < debugger.breakpoints.len() {
+                    let b = debugger.breakpoints.remove(i);
+                    write!(out, "Deleted breakpoint {}: {}", i, b).unwrap();
+                } else {
+                    write!(out, "No breakpoint {}", i).unwrap();
+                }
+            }
+            Command::Breakpoints => {
+                for (i, b) in debugger.breakpoints.iter().enumerate() {
+                    writeln!(out, "{}: {}", i, b).unwrap();
+                }
+                write!(out, "{} breakpoints", debugger.breakpoints.len()).unwrap();
+            }
+            Command::Registers => {
+                let registers: Vec<String> = debugger
+                    .machine
+                    .registers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| format!("{}={}", (b'a' + i as u8) as char, v))
+                    .collect();
+                write!(out, "{}", registers.join(" ")).unwrap();
+            }
+            Command::Set(r, v) => {
+                if r.index() < debugger.machine.registers.len() {
+                    debugger.machine.set(r, v);
+                    write!(out, "{} = {}", r, v).unwrap();
+                } else {
+                    write!(out, "No register {} in this machine", r).unwrap();
+                }
+            }
+            Command::Send(v) => {
+                debugger.machine.inbox.send(v);
+                write!(
+                    out,
+                    "Inbox has {} pending",
+                    debugger.machine.inbox.pending()
+                )
+                .unwrap();
+            }
+            Command::Trace(n) => {
+                let start = debugger.trace.len().saturating_sub(n);
+                for entry in debugger.trace[start..].iter() {
+                    writeln!(out, "{}", entry).unwrap();
+                }
+                write!(out, "{}", describe_position(debugger)).unwrap();
+            }
+            Command::List => {
+                let lines: Vec<String> = debugger
+                    .machine
+                    .program
+                    .iter()
+                    .enumerate()
+                    .map(|(pc, i)| {
+                        let marker = if pc == debugger.machine.pc { '>' } else { ' ' };
+                        format!("{}{:>4}: {}", marker, pc, i)
+                    })
+                    .collect();
+                write!(out, "{}", lines.join("\n")).unwrap();
+            }
+            Command::Help => write!(out, "{}", HELP).unwrap(),
+            Command::Quit => {}
+        }
+        out
+    }
+
+    pub fn parse_dialect(s: &str) -> Result<isa::Dialect, String> {
+        match s {
+            "duet" => Ok(isa::Dialect::Duet),
+            "coprocessor" => Ok(isa::Dialect::Coprocessor),
+            _ => Err(format!("Expected 'duet' or 'coprocessor', got: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::channel::*;
+    use super::compiled::*;
+    use super::isa::*;
+    use super::machine::*;
+    use super::optimise::*;
+    use super::repl::*;
+    use super::scheduler::*;
+    use super::trace::*;
+
+    #[test]
+    fn test_parse() {
+        let program = "set a 1\nadd a b\nsnd -3\nrcv z\njgz a -1";
+        let instructions = parse_program(Dialect::Duet, program).unwrap();
+        assert_eq!(
+            instructions[1],
+            Instruction::Add(
+                Register::new('a').unwrap(),
+                Operand::Register(Register::new('b').unwrap())
+            )
+        );
+        let printed: Vec<String> = instructions.iter().map(|i| i.to_string()).collect();
+        assert_eq!(printed.join("\n"), program);
+    }
+
+    #[test]
+    fn test_parse_dialect() {
+        let error = parse_program(Dialect::Coprocessor, "set a 1\nsnd a").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(error.expected, "a Coprocessor instruction");
+        let error = parse_instruction(Dialect::Coprocessor, "sub i 1").unwrap_err();
+        assert_eq!(error.expected, "a register from 'a' to 'h'");
+        assert!(parse_instruction(Dialect::Duet, "sub i 1").is_err());
+        assert!(parse_instruction(Dialect::Duet, "rcv 1").is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let program =
+            parse_program(Dialect::Duet, "set a 3\nmul a a\nsnd a\nrcv b\njgz 1 -10").unwrap();
+        let mut machine = Machine::new(Dialect::Duet, &program);
+        assert_eq!(machine.run(), Effect::Send(9));
+        assert_eq!(machine.run(), Effect::Blocked);
+        machine.inbox.send(4);
+        assert_eq!(machine.run(), Effect::Received(4));
+        assert_eq!(machine.run(), Effect::Halted);
+        assert_eq!(machine.registers[..2], [9, 4]);
+    }
+
+    #[test]
+    fn test_channel() {
+        let mut channel = Channel::new();
+        channel.send(1);
+        channel.send(2);
+        assert_eq!(channel.receive(), Some(1));
+        assert_eq!(channel.pending(), 1);
+        assert_eq!(channel.last_sent(), Some(2));
+        assert_eq!(channel.receive(), Some(2));
+        assert_eq!(channel.receive(), None);
+        assert_eq!((channel.sent(), channel.received()), (2, 2));
+    }
+
+    fn machines(program: &[Instruction], n: usize) -> Vec<Machine<'_>> {
+        (0..n)
+            .map(|i| {
+                let mut machine = Machine::new(Dialect::Duet, program);
+                machine.set(Register::new('p').unwrap(), i as i64);
+                machine
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ring() {
+        // Each machine passes on what it gets, plus its own ID, three times.
+        let program = "jgz p 2\nsnd 0\nset i 3\nrcv a\nadd a p\nsnd a\nadd i -1\njgz i -4";
+        let program = parse_program(Dialect::Duet, program).unwrap();
+        let mut scheduler = Scheduler::new(machines(&program, 3), Topology::ring(3)).unwrap();
+        let report = scheduler.run();
+        assert_eq!(report.outcome, Outcome::Halted);
+        let sent: Vec<usize> = report.machines.iter().map(|s| s.sent).collect();
+        assert_eq!(sent, [4, 3, 3]);
+        assert_eq!(scheduler.machines[0].get(Register::new('a').unwrap()), 9);
+        // Machine 0's last message is never picked up.
+        assert_eq!(scheduler.machines[1].inbox.pending(), 1);
+    }
+
+    #[test]
+    fn test_star() {
+        // The hub sends 7 to every spoke, and each spoke replies once. The hub
+        // then waits for a third reply which never comes.
+        let program = "jgz p 4\nsnd 7\nrcv a\nrcv b\nrcv a\nadd a p\nsnd a";
+        let program = parse_program(Dialect::Duet, program).unwrap();
+        let mut scheduler = Scheduler::new(machines(&program, 3), Topology::star(3)).unwrap();
+        let report = scheduler.run();
+        assert_eq!(report.outcome, Outcome::Deadlock);
+        let stats: Vec<(usize, usize)> = report
+            .machines
+            .iter()
+            .map(|s| (s.sent, s.received))
+            .collect();
+        assert_eq!(stats, [(1, 2), (1, 1), (1, 1)]);
+        assert_eq!(scheduler.machines[0].get(Register::new('a').unwrap()), 8);
+        assert_eq!(scheduler.machines[0].get(Register::new('b').unwrap()), 9);
+
+        let error = Scheduler::new(machines(&program, 2), Topology::star(3)).err();
+        assert_eq!(
+            error.as_deref(),
+            Some("Topology is for 3 machines, but there are 2")
+        );
+        let topology = Topology {
+            routes: vec![vec![1], vec![2]],
+        };
+        let error = Scheduler::new(machines(&program, 2), topology).err();
+        assert_eq!(
+            error.as_deref(),
+            Some("Topology routes machine 1 to machine 2, which doesn't exist")
+        );
+    }
+
+    #[test]
+    fn test_debugger() {
+        let program = parse_program(Dialect::Duet, "set a 5\nsnd a\nrcv b\nmul b a").unwrap();
+        let a = Register::new('a').unwrap();
+        let b = Register::new('b').unwrap();
+        let mut debugger = Debugger::new(Machine::new(Dialect::Duet, &program));
+        debugger
+            .breakpoints
+            .push(Breakpoint::Register(b, Comparison::Greater, 10));
+        assert_eq!(debugger.run(None), Stop::Blocked);
+        assert_eq!(debugger.outbox, [5]);
+        debugger.machine.inbox.send(3);
+        assert_eq!(debugger.run(None), Stop::Breakpoint(0));
+        assert_eq!(debugger.machine.get(b), 15);
+        assert_eq!(
+            debugger.trace.last().unwrap().to_string(),
+            "   3: mul b a  b: 3 -> 15"
+        );
+
+        assert_eq!(debugger.step_back().unwrap().pc, 3);
+        let entry = debugger.step_back().unwrap();
+        assert_eq!(entry.event, Some(ChannelEvent::Received(3)));
+        assert_eq!(debugger.machine.inbox.pending(), 1);
+        debugger.step_back();
+        assert!(debugger.outbox.is_empty());
+        debugger.step_back();
+        assert_eq!((debugger.machine.pc, debugger.machine.get(a)), (0, 0));
+        assert_eq!(debugger.step_back(), None);
+
+        debugger.breakpoints = vec![Breakpoint::Pc(2)];
+        assert_eq!(debugger.run(None), Stop::Breakpoint(0));
+        assert_eq!(debugger.run(Some(1)), Stop::Limit);
+        assert_eq!(debugger.machine.pc, 3);
+        assert_eq!(debugger.run(None), Stop::Halted);
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("step"), Ok(Command::Step(1)));
+        assert_eq!(parse_command("c 100"), Ok(Command::Continue(Some(100))));
+        assert_eq!(
+            parse_command("break h != 0"),
+            Ok(Command::Break(Breakpoint::Register(
+                Register::new('h').unwrap(),
+                Comparison::NotEqual,
+                0
+            )))
+        );
+        assert_eq!(
+            parse_command("break pc 12"),
+            Ok(Command::Break(Breakpoint::Pc(12)))
+        );
+        assert!(parse_command("break h ~ 0").is_err());
+        assert!(parse_command("step 1 2").is_err());
+        assert!(parse_command("").is_err());
+    }
+
+    #[test]
+    fn test_execute_missing_register() {
+        let program = parse_program(Dialect::Coprocessor, "set a 1\nset b 2").unwrap();
+        let mut debugger = Debugger::new(Machine::new(Dialect::Coprocessor, &program));
+        let command = parse_command("break z > 0").unwrap();
+        assert_eq!(
+            execute(&mut debugger, &command),
+            "No register z in this machine"
+        );
+        assert!(debugger.breakpoints.is_empty());
+        let set = parse_command("set z 1").unwrap();
+        assert_eq!(
+            execute(&mut debugger, &set),
+            "No register z in this machine"
+        );
+
+        // Even if one gets in some other way, it never fires.
+        let z = Register::new('z').unwrap();
+        debugger
+            .breakpoints
+            .push(Breakpoint::Register(z, Comparison::Equal, 0));
+        assert_eq!(debugger.run(None), Stop::Halted);
+    }
+
+    // Counts the composite numbers in b..=c in steps of 17, the slow way.
+    const COPROCESSOR: &str =
+        "set f 1\nset d 2\nset e 2\nset g d\nmul g e\nsub g b\njnz g 2\nset f 0\n\
+        sub e -1\nset g e\nsub g b\njnz g -8\nsub d -1\nset g d\nsub g b\njnz g -13\n\
+        jnz f 2\nsub h -1\nset g b\nsub g c\njnz g 2\njnz 1 3\nsub b -17\njnz 1 -23";
+
+    #[test]
+    fn test_cfg() {
+        let program = parse_program(Dialect::Coprocessor, COPROCESSOR).unwrap();
+        let cfg = Cfg::new(&program);
+        let starts: Vec<usize> = cfg.blocks.iter().map(|b| b.start).collect();
+        assert_eq!(starts, [0, 2, 3, 7, 8, 12, 16, 17, 18, 21, 22]);
+        assert_eq!(cfg.blocks[9].successors, [Successor::Halt]);
+        let loops: Vec<(usize, usize)> = cfg.loops().iter().map(|l| (l.head, l.latch)).collect();
+        assert_eq!(loops, [(3, 11), (2, 15), (0, 23)]);
+    }
+
+    #[test]
+    fn test_optimise() {
+        let program = parse_program(Dialect::Coprocessor, COPROCESSOR).unwrap();
+        let optimised = optimise(&program);
+        let shortcut = optimised.shortcuts[2].unwrap();
+        assert_eq!(shortcut.exit, 16);
+        assert!(matches!(shortcut.idiom, Idiom::FactorSearch { .. }));
+        assert!(optimised.to_string().contains("   2: if b = x * y"));
+
+        let b = Register::new('b').unwrap();
+        let c = Register::new('c').unwrap();
+        for &(start, end) in &[(77, 77 + 17 * 6), (19, 19 + 17 * 3), (79, 79)] {
+            let mut plain = Machine::new(Dialect::Coprocessor, &program);
+            plain.set(b, start);
+            plain.set(c, end);
+            let mut fast = plain.clone();
+            assert_eq!(plain.run(), Effect::Halted);
+            assert_eq!(optimised.run(&mut fast), Effect::Halted);
+            assert_eq!(plain.registers, fast.registers);
+        }
+    }
+
+    #[test]
+    fn test_count_up() {
+        let program = "set a 3\nsub a -1\nset g a\nsub g b\njnz g -3\nsub b -1";
+        let program = parse_program(Dialect::Coprocessor, program).unwrap();
+        let optimised = optimise(&program);
+        assert_eq!(
+            optimised.to_string(),
+            "   0: set a 3\n   1: a = b; g = 0\n   5: sub b -1\n"
+        );
+        for &start in &[4, 10, 100] {
+            let mut plain = Machine::new(Dialect::Coprocessor, &program);
+            plain.set(Register::new('b').unwrap(), start);
+            let mut fast = plain.clone();
+            plain.run();
+            optimised.run(&mut fast);
+            assert_eq!(plain.registers, fast.registers);
+        }
+    }
+
+    #[test]
+    fn test_compiled() {
+        let program = "set i 5\nset a 1\nmul a 3\nmod a 7\nadd i -1\njgz i -3\nsnd a\n\
+            rcv b\nset j 2\njgz 1 j\nadd b 100\nadd b b\njgz 0 -100\njgz b -1000";
+        let program = parse_program(Dialect::Duet, program).unwrap();
+        let code = compile(&program);
+        assert_eq!(code.ops[4], Op::AddI(8, -1));
+        assert_eq!(code.ops[5], Op::Jgz(8, 2));
+        assert_eq!(code.ops[9], Op::Interpret);
+        assert_eq!(code.ops[12], Op::Nop);
+        assert_eq!(code.ops[13], Op::Jgz(1, 14));
+
+        let mut effects = vec![];
+        for &backend in &[Backend::Interpreter, Backend::Compiled] {
+            let mut machine = Machine::with_backend(Dialect::Duet, &program, backend);
+            let mut seen = vec![machine.run(), machine.run()];
+            machine.inbox.send(4);
+            seen.extend(vec![machine.run(), machine.run()]);
+            effects.push((seen, machine.pc, machine.registers));
+        }
+        assert_eq!(effects[0], effects[1]);
+        assert_eq!(
+            effects[0].0,
+            [
+                Effect::Send(5),
+                Effect::Blocked,
+                Effect::Received(4),
+                Effect::Halted
+            ]
+        );
+        assert_eq!("compiled".parse(), Ok(Backend::Compiled));
+        assert_eq!(Backend::default(), Backend::Compiled);
+
+        // Jumps too far to add to the PC leave the program.
+        let program = "set a 9223372036854775807\njgz 1 9223372036854775807\njgz 1 a";
+        let program = parse_program(Dialect::Duet, program).unwrap();
+        assert_eq!(compile(&program).ops[1], Op::Jump(3));
+        let halts = parse_program(Dialect::Duet, "set a 9223372036854775807\njgz 1 a").unwrap();
+        for &backend in &[Backend::Interpreter, Backend::Compiled] {
+            let mut machine = Machine::with_backend(Dialect::Duet, &halts, backend);
+            assert_eq!(machine.run(), Effect::Halted);
+            assert_eq!(machine.pc, 2);
+        }
+        assert_eq!(Cfg::new(&program).blocks[0].successors, [Successor::Halt]);
+
+        let program = "sub a -9223372036854775808\nsub a 9223372036854775807";
+        let program = parse_program(Dialect::Coprocessor, program).unwrap();
+        let code = compile(&program);
+        assert_eq!(code.ops, [Op::Interpret, Op::AddI(0, -i64::MAX)]);
+    }
+}
@@ -0,0 +1,50 @@
+use std::io::{self, BufRead, Write};
+use std::process;
+use vm::isa;
+use vm::machine::Machine;
+use vm::repl::{self, Command};
+use vm::trace::Debugger;
+
+const USAGE: &str = "Usage: vm <duet|coprocessor> <PROGRAM>";
+
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (dialect, path) = match &args[..] {
+        [dialect, path] => match repl::parse_dialect(dialect) {
+            Ok(dialect) => (dialect, path),
+            Err(e) => {
+                eprintln!("{}\n{}", e, USAGE);
+                process::exit(2);
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    let program = common::input::from_path(path)?;
+    let program = match isa::parse_program(dialect, &program) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("Could not parse {}: {}", path, e);
+            process::exit(1);
+        }
+    };
+
+    let mut debugger = Debugger::new(Machine::new(dialect, &program));
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    write!(stdout, "(vm) ")?;
+    stdout.flush()?;
+    for line in stdin.lock().lines() {
+        let line = line?;
+        match repl::parse_command(&line) {
+            Ok(Command::Quit) => break,
+            Ok(command) => println!("{}", repl::execute(&mut debugger, &command)),
+            Err(e) => println!("{}", e),
+        }
+        write!(stdout, "(vm) ")?;
+        stdout.flush()?;
+    }
+    Ok(())
+}